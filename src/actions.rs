@@ -0,0 +1,692 @@
+use bevy::{ecs::system::SystemParam, input::gamepad::GamepadConnectionEvent, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use crate::{game::{GameState, SimulationSet}, options::{user_config_path, LaunchOptions}, splash::despawn_screen};
+
+const SCREEN_FONT_SIZE: f32 = 32.0;
+const SCREEN_TEXT_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+const SCREEN_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+
+// How far a stick has to be pushed to count as held in that direction.
+const STICK_THRESHOLD: f32 = 0.5;
+// Pushes this small are drift from a worn stick rather than the player.
+const STICK_DEAD_ZONE: f32 = 0.15;
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_resource::<InputBindings>()
+         .add_systems(Update, (pause_on_gamepad_disconnect).run_if(in_state(GameState::InGame)).in_set(SimulationSet));
+  }
+}
+
+// The controls screen, reached with B from the splash screen.
+pub struct RebindScreenPlugin;
+
+impl Plugin for RebindScreenPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_resource::<RebindCursor>()
+         .add_systems(OnEnter(GameState::Rebinding), rebind_screen_setup)
+         .add_systems(Update, (rebind_input, update_rebind_screen).chain().run_if(in_state(GameState::Rebinding)))
+         .add_systems(OnExit(GameState::Rebinding), (save_bindings, despawn_screen::<OnRebindScreen>));
+  }
+}
+
+// What the player wants to do, whatever it is bound to.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+  MoveUp,
+  MoveDown,
+  MoveLeft,
+  MoveRight,
+  Run,
+  Attack,
+  Pause,
+  Start,
+  Continue,
+  HighScores,
+  Rebind,
+}
+
+impl Action {
+  pub const ALL: [Action; 11] = [
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Run,
+    Action::Attack,
+    Action::Pause,
+    Action::Start,
+    Action::Continue,
+    Action::HighScores,
+    Action::Rebind,
+  ];
+
+  fn default_bindings(self) -> Vec<Binding> {
+    use AxisDirection::{Negative, Positive};
+    use Binding::{Key, Mouse, Pad, Stick};
+
+    match self {
+      Action::MoveUp => vec![Key(KeyCode::Up), Key(KeyCode::W), Stick(GamepadAxisType::LeftStickY, Positive), Pad(GamepadButtonType::DPadUp)],
+      Action::MoveDown => vec![Key(KeyCode::Down), Key(KeyCode::S), Stick(GamepadAxisType::LeftStickY, Negative), Pad(GamepadButtonType::DPadDown)],
+      Action::MoveLeft => vec![Key(KeyCode::Left), Key(KeyCode::A), Stick(GamepadAxisType::LeftStickX, Negative), Pad(GamepadButtonType::DPadLeft)],
+      Action::MoveRight => vec![Key(KeyCode::Right), Key(KeyCode::D), Stick(GamepadAxisType::LeftStickX, Positive), Pad(GamepadButtonType::DPadRight)],
+      Action::Run => vec![Key(KeyCode::ShiftLeft), Key(KeyCode::ShiftRight), Pad(GamepadButtonType::RightTrigger2)],
+      Action::Attack => vec![Key(KeyCode::Space), Mouse(MouseButton::Left), Pad(GamepadButtonType::South), Pad(GamepadButtonType::West)],
+      Action::Pause => vec![Key(KeyCode::Escape), Pad(GamepadButtonType::Start)],
+      // Was S, which also moves down. Nothing used in play, so a held attack
+      // or step can't skip a banner.
+      Action::Start => vec![Key(KeyCode::Return), Pad(GamepadButtonType::Start)],
+      Action::Continue => vec![Key(KeyCode::C), Pad(GamepadButtonType::North)],
+      Action::HighScores => vec![Key(KeyCode::H), Pad(GamepadButtonType::East)],
+      Action::Rebind => vec![Key(KeyCode::B), Pad(GamepadButtonType::Select)],
+    }
+  }
+
+  // Two actions can't share a binding, except Pause and Start: one is only read
+  // in play and the other only outside it, so the gamepad's Start does both.
+  fn clashes_with(self, other: Action) -> bool {
+    self != other && !matches!((self, other), (Action::Pause, Action::Start) | (Action::Start, Action::Pause))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisDirection {
+  Positive,
+  Negative,
+}
+
+impl AxisDirection {
+  pub fn sign(self) -> f32 {
+    match self {
+      AxisDirection::Positive => 1.,
+      AxisDirection::Negative => -1.,
+    }
+  }
+}
+
+// Gamepad inputs count on any connected gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+  Key(KeyCode),
+  Mouse(MouseButton),
+  Pad(GamepadButtonType),
+  // Held while the stick is pushed past STICK_THRESHOLD that way. Sticks
+  // never count as just pressed.
+  Stick(GamepadAxisType, AxisDirection),
+}
+
+impl Binding {
+  fn on_gamepad(&self) -> bool {
+    matches!(self, Binding::Pad(_) | Binding::Stick(..))
+  }
+}
+
+impl std::fmt::Display for Binding {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Binding::Key(key) => write!(f, "{:?}", key),
+      Binding::Mouse(button) => write!(f, "Mouse {:?}", button),
+      Binding::Pad(button) => write!(f, "Pad {:?}", button),
+      Binding::Stick(axis, direction) => write!(f, "{:?} {:?}", axis, direction),
+    }
+  }
+}
+
+// What each action is bound to, kept in axol/bindings.ron in the user config
+// directory. Only regular sessions read and write the file, so recordings
+// always play back with the defaults.
+#[derive(Resource)]
+pub struct InputBindings {
+  bindings: HashMap<Action, Vec<Binding>>,
+  path: Option<PathBuf>,
+}
+
+impl FromWorld for InputBindings {
+  fn from_world(world: &mut World) -> Self {
+    let path = world.get_resource::<LaunchOptions>()
+      .filter(|options| options.uses_user_data())
+      .and_then(|_| user_config_path("bindings.ron"));
+
+    InputBindings::read(path)
+  }
+}
+
+impl InputBindings {
+  // Every action on its default bindings, saved to path if there is one.
+  fn new(path: Option<PathBuf>) -> Self {
+    InputBindings {
+      bindings: Action::ALL.iter().map(|action| (*action, action.default_bindings())).collect(),
+      path,
+    }
+  }
+
+  // The bindings saved at path, if any. Actions missing from the file keep
+  // their defaults.
+  fn read(path: Option<PathBuf>) -> Self {
+    let mut input_bindings = InputBindings::new(path);
+
+    if let Some(path) = input_bindings.path.as_ref().filter(|path| path.is_file()) {
+      match InputBindings::load(path) {
+        Ok(bindings) => input_bindings.bindings.extend(bindings),
+        Err(e) => warn!("Unable to read bindings from {}: {}", path.display(), e),
+      }
+    }
+
+    input_bindings
+  }
+
+  fn load(path: &Path) -> Result<HashMap<Action, Vec<Binding>>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+  }
+
+  fn save(&self) {
+    let Some(path) = &self.path else {
+      return;
+    };
+
+    let result = path.parent()
+      .map_or(Ok(()), std::fs::create_dir_all)
+      .map_err(|e| e.to_string())
+      .and_then(|_| ron::ser::to_string_pretty(&self.bindings, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string()))
+      .and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+      error!("Unable to save bindings to {}: {}", path.display(), e);
+    }
+  }
+
+  pub fn get(&self, action: Action) -> &[Binding] {
+    self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+  }
+
+  // The first key bound to an action, for pressing it on the player's behalf.
+  pub fn key(&self, action: Action) -> Option<KeyCode> {
+    self.get(action).iter().find_map(|binding| match binding {
+      Binding::Key(key) => Some(*key),
+      _ => None,
+    })
+  }
+
+  // The first gamepad button or stick bound to an action.
+  pub fn pad(&self, action: Action) -> Option<Binding> {
+    self.get(action).iter().copied().find(|binding| matches!(binding, Binding::Pad(_) | Binding::Stick(..)))
+  }
+
+  // e.g. "Up or W", for telling the player what to press.
+  pub fn describe(&self, action: Action) -> String {
+    let names: Vec<String> = self.get(action).iter().map(Binding::to_string).collect();
+
+    if names.is_empty() {
+      "(unbound)".to_string()
+    } else {
+      names.join(" or ")
+    }
+  }
+
+  // Another action already on this binding that the action can't share it with.
+  fn clash(&self, action: Action, binding: Binding) -> Option<Action> {
+    Action::ALL.iter().copied()
+      .filter(|other| action.clashes_with(*other))
+      .find(|other| self.get(*other).contains(&binding))
+  }
+
+  // Swaps the action's keyboard and mouse bindings, or its gamepad ones, for
+  // this one, keeping those on the other kind of device. Refused when another
+  // action it clashes with is on the binding, which is returned.
+  fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+    if let Some(other) = self.clash(action, binding) {
+      return Err(other);
+    }
+
+    let bindings = self.bindings.entry(action).or_default();
+
+    bindings.retain(|bound| bound.on_gamepad() != binding.on_gamepad());
+
+    // Keyboard and mouse first, as the defaults list them.
+    let at = if binding.on_gamepad() { bindings.len() } else { 0 };
+    bindings.insert(at, binding);
+
+    Ok(())
+  }
+
+  // Puts the action back on its defaults, less any another action has been
+  // bound to since.
+  fn reset(&mut self, action: Action) {
+    let defaults = action.default_bindings().into_iter()
+      .filter(|binding| self.clash(action, *binding).is_none())
+      .collect();
+
+    self.bindings.insert(action, defaults);
+  }
+}
+
+// Gameplay asks this rather than the keyboard, mouse and gamepads.
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+  bindings: Res<'w, InputBindings>,
+  keys: Res<'w, Input<KeyCode>>,
+  buttons: Res<'w, Input<MouseButton>>,
+  gamepads: Res<'w, Gamepads>,
+  pad_buttons: Res<'w, Input<GamepadButton>>,
+  pad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl Actions<'_> {
+  pub fn pressed(&self, action: Action) -> bool {
+    self.bindings.get(action).iter().any(|binding| self.pressed_binding(binding))
+  }
+
+  fn pressed_binding(&self, binding: &Binding) -> bool {
+    match binding {
+      Binding::Key(key) => self.keys.pressed(*key),
+      Binding::Mouse(button) => self.buttons.pressed(*button),
+      Binding::Pad(button) => self.gamepads.iter().any(|gamepad| self.pad_buttons.pressed(GamepadButton::new(gamepad, *button))),
+      Binding::Stick(axis, direction) => self.gamepads.iter().any(|gamepad| {
+        self.pad_axes.get(GamepadAxis::new(gamepad, *axis)).is_some_and(|value| value * direction.sign() > STICK_THRESHOLD)
+      }),
+    }
+  }
+
+  pub fn just_pressed(&self, action: Action) -> bool {
+    self.bindings.get(action).iter().any(|binding| match binding {
+      Binding::Key(key) => self.keys.just_pressed(*key),
+      Binding::Mouse(button) => self.buttons.just_pressed(*button),
+      Binding::Pad(button) => self.gamepads.iter().any(|gamepad| self.pad_buttons.just_pressed(GamepadButton::new(gamepad, *button))),
+      Binding::Stick(..) => false,
+    })
+  }
+
+  pub fn describe(&self, action: Action) -> String {
+    self.bindings.describe(action)
+  }
+
+  // How far an action is held from 0 to 1: all the way for keys and buttons,
+  // as far as the stick is pushed past its dead zone for sticks.
+  pub fn strength(&self, action: Action) -> f32 {
+    self.bindings.get(action).iter().map(|binding| match binding {
+      Binding::Stick(axis, direction) => self.gamepads.iter()
+        .filter_map(|gamepad| self.pad_axes.get(GamepadAxis::new(gamepad, *axis)))
+        .map(|value| ((value * direction.sign() - STICK_DEAD_ZONE) / (1. - STICK_DEAD_ZONE)).clamp(0., 1.))
+        .fold(0., f32::max),
+      _ if self.pressed_binding(binding) => 1.,
+      _ => 0.,
+    })
+    .fold(0., f32::max)
+  }
+
+  // The direction to move in from the move actions, no longer than 1 so
+  // diagonals aren't faster.
+  pub fn movement(&self) -> Vec2 {
+    Vec2::new(
+      self.strength(Action::MoveRight) - self.strength(Action::MoveLeft),
+      self.strength(Action::MoveUp) - self.strength(Action::MoveDown),
+    ).clamp_length_max(1.)
+  }
+}
+
+// Losing the controller mid-run shouldn't leave the player defenceless.
+fn pause_on_gamepad_disconnect(
+  mut connections: EventReader<GamepadConnectionEvent>,
+  mut next_state: ResMut<NextState<GameState>>,
+) {
+  if connections.read().any(|event| event.disconnected()) {
+    next_state.set(GameState::Paused);
+  }
+}
+
+#[derive(Default, Resource)]
+struct RebindCursor {
+  selected: usize,
+  // Waiting for the key or button to bind the selected action to.
+  listening: bool,
+  // The last binding refused, and the action already on it.
+  refused: Option<(Binding, Action)>,
+}
+
+// Tag component used to tag entities added on the controls screen
+#[derive(Component)]
+struct OnRebindScreen;
+
+#[derive(Component)]
+struct RebindText;
+
+fn rebind_screen_setup(
+  mut commands: Commands,
+  mut cursor: ResMut<RebindCursor>,
+) {
+  *cursor = RebindCursor::default();
+
+  commands
+      .spawn((
+          NodeBundle {
+              style: Style {
+                  position_type: PositionType::Absolute,
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  width: Val::Percent(100.0),
+                  height: Val::Percent(100.0),
+                  ..default()
+              },
+              background_color: SCREEN_BACKGROUND_COLOR.into(),
+              ..default()
+          },
+          OnRebindScreen,
+      ))
+      .with_children(|parent| {
+          parent.spawn((
+              RebindText,
+              TextBundle::from_section(
+                  "",
+                  TextStyle {
+                      font_size: SCREEN_FONT_SIZE,
+                      color: SCREEN_TEXT_COLOR,
+                      ..default()
+                  },
+              ),
+          ));
+      });
+}
+
+// The screen is driven by fixed keys and gamepad buttons so it can't be locked
+// out by a bad binding.
+fn rebind_input(
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  pad_buttons: Res<Input<GamepadButton>>,
+  mut cursor: ResMut<RebindCursor>,
+  mut bindings: ResMut<InputBindings>,
+  mut next_state: ResMut<NextState<GameState>>,
+) {
+  let action = Action::ALL[cursor.selected];
+
+  if cursor.listening {
+    let binding = if keys.just_pressed(KeyCode::Escape) {
+      cursor.listening = false;
+      None
+    } else if let Some(key) = keys.get_just_pressed().next() {
+      Some(Binding::Key(*key))
+    } else if let Some(button) = buttons.get_just_pressed().next() {
+      Some(Binding::Mouse(*button))
+    } else {
+      pad_buttons.get_just_pressed().next().map(|button| Binding::Pad(button.button_type))
+    };
+
+    if let Some(binding) = binding {
+      cursor.refused = bindings.rebind(action, binding).err().map(|other| (binding, other));
+      cursor.listening = false;
+    }
+    return;
+  }
+
+  let just_pressed = |key: KeyCode, pad_button: GamepadButtonType| {
+    keys.just_pressed(key) || pad_buttons.get_just_pressed().any(|button| button.button_type == pad_button)
+  };
+
+  if just_pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+    cursor.selected = cursor.selected.checked_sub(1).unwrap_or(Action::ALL.len() - 1);
+  }
+  if just_pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+    cursor.selected = (cursor.selected + 1) % Action::ALL.len();
+  }
+  if just_pressed(KeyCode::Return, GamepadButtonType::South) {
+    cursor.listening = true;
+    cursor.refused = None;
+  }
+  if just_pressed(KeyCode::Back, GamepadButtonType::North) {
+    bindings.reset(action);
+  }
+  if just_pressed(KeyCode::Escape, GamepadButtonType::East) {
+    next_state.set(GameState::Splash);
+  }
+}
+
+fn update_rebind_screen(
+  cursor: Res<RebindCursor>,
+  bindings: Res<InputBindings>,
+  mut text: Query<&mut Text, With<RebindText>>,
+) {
+  let mut text = text.single_mut();
+  let mut value = String::from("Controls\n\n");
+
+  for (index, action) in Action::ALL.iter().enumerate() {
+    let marker = if index == cursor.selected { ">" } else { " " };
+    let bound = if index == cursor.selected && cursor.listening {
+      "press a key or button...".to_string()
+    } else {
+      bindings.describe(*action)
+    };
+
+    let name = format!("{:?}", action);
+
+    value.push_str(&format!("{} {:<12} {}\n", marker, name, bound));
+  }
+
+  if let Some((binding, other)) = cursor.refused {
+    value.push_str(&format!("\n{} is already bound to {:?}\n", binding, other));
+  }
+
+  value.push_str("\nUp/Down to choose, Enter (A) to rebind, Backspace (Y) to reset, Escape (B) to go back");
+  text.sections[0].value = value;
+}
+
+fn save_bindings(bindings: Res<InputBindings>) {
+  bindings.save();
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::{ecs::system::RunSystemOnce, input::{gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadEvent, GamepadInfo}, InputPlugin}};
+  use super::*;
+
+  const PAD: Gamepad = Gamepad { id: 0 };
+
+  // Input goes through the same events a real gamepad sends.
+  fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((InputPlugin, ActionsPlugin))
+       .add_state::<GameState>();
+    app.world.insert_resource(NextState(Some(GameState::InGame)));
+    app.update();
+    app
+  }
+
+  fn send(app: &mut App, events: impl IntoIterator<Item = GamepadEvent>) {
+    for event in events {
+      app.world.send_event(event);
+    }
+    app.update();
+  }
+
+  fn connection(connection: GamepadConnection) -> GamepadEvent {
+    GamepadEvent::Connection(GamepadConnectionEvent::new(PAD, connection))
+  }
+
+  fn connected() -> GamepadEvent {
+    connection(GamepadConnection::Connected(GamepadInfo { name: "Test".to_string() }))
+  }
+
+  fn button(button: GamepadButtonType, value: f32) -> GamepadEvent {
+    GamepadEvent::Button(GamepadButtonChangedEvent::new(PAD, button, value))
+  }
+
+  fn stick(axis: GamepadAxisType, value: f32) -> GamepadEvent {
+    GamepadEvent::Axis(GamepadAxisChangedEvent::new(PAD, axis, value))
+  }
+
+  fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("axol-{}-{}.ron", name, std::process::id()))
+  }
+
+  #[test]
+  fn default_bindings_dont_clash() {
+    let bindings = InputBindings::new(None);
+
+    for action in Action::ALL {
+      for binding in bindings.get(action) {
+        assert_eq!(bindings.clash(action, *binding), None, "{:?} on {}", action, binding);
+      }
+    }
+
+    // S moves down, so it can't start the game as well.
+    assert_eq!(bindings.key(Action::Start), Some(KeyCode::Return));
+  }
+
+  #[test]
+  fn rebinding_refuses_a_binding_another_action_is_on() {
+    let mut bindings = InputBindings::new(None);
+
+    assert_eq!(bindings.rebind(Action::Attack, Binding::Key(KeyCode::W)), Err(Action::MoveUp));
+    assert_eq!(bindings.rebind(Action::Run, Binding::Pad(GamepadButtonType::South)), Err(Action::Attack));
+    assert_eq!(bindings.get(Action::Attack), Action::Attack.default_bindings().as_slice());
+    assert_eq!(bindings.get(Action::Run), Action::Run.default_bindings().as_slice());
+
+    // Never read on the same screen.
+    assert_eq!(bindings.rebind(Action::Pause, Binding::Key(KeyCode::Return)), Ok(()));
+  }
+
+  #[test]
+  fn rebinding_replaces_only_the_same_kind_of_device() {
+    let mut bindings = InputBindings::new(None);
+
+    assert_eq!(bindings.rebind(Action::Attack, Binding::Key(KeyCode::J)), Ok(()));
+    assert_eq!(bindings.get(Action::Attack), &[
+      Binding::Key(KeyCode::J),
+      Binding::Pad(GamepadButtonType::South),
+      Binding::Pad(GamepadButtonType::West),
+    ]);
+
+    assert_eq!(bindings.rebind(Action::Attack, Binding::Pad(GamepadButtonType::RightTrigger)), Ok(()));
+    assert_eq!(bindings.get(Action::Attack), &[Binding::Key(KeyCode::J), Binding::Pad(GamepadButtonType::RightTrigger)]);
+  }
+
+  #[test]
+  fn resetting_skips_defaults_taken_since() {
+    let mut bindings = InputBindings::new(None);
+    bindings.rebind(Action::Attack, Binding::Key(KeyCode::J)).unwrap();
+    bindings.rebind(Action::Continue, Binding::Key(KeyCode::Space)).unwrap();
+
+    bindings.reset(Action::Attack);
+
+    assert_eq!(bindings.get(Action::Attack), &[
+      Binding::Mouse(MouseButton::Left),
+      Binding::Pad(GamepadButtonType::South),
+      Binding::Pad(GamepadButtonType::West),
+    ]);
+  }
+
+  #[test]
+  fn saved_bindings_are_read_back() {
+    let path = temp_path("saved-bindings");
+    let mut bindings = InputBindings::new(Some(path.clone()));
+    bindings.rebind(Action::Attack, Binding::Key(KeyCode::J)).unwrap();
+    bindings.rebind(Action::Run, Binding::Pad(GamepadButtonType::LeftTrigger2)).unwrap();
+    bindings.save();
+
+    let read = InputBindings::read(Some(path.clone()));
+    std::fs::remove_file(&path).unwrap();
+
+    for action in Action::ALL {
+      assert_eq!(read.get(action), bindings.get(action), "{:?}", action);
+    }
+  }
+
+  #[test]
+  fn actions_missing_from_the_file_keep_their_defaults() {
+    let path = temp_path("partial-bindings");
+    std::fs::write(&path, "{Attack: [Key(J)]}").unwrap();
+
+    let read = InputBindings::read(Some(path.clone()));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.get(Action::Attack), &[Binding::Key(KeyCode::J)]);
+    assert_eq!(read.get(Action::MoveUp), Action::MoveUp.default_bindings().as_slice());
+  }
+
+  #[test]
+  fn stick_moves_as_far_as_it_is_pushed() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+    send(&mut app, [stick(GamepadAxisType::LeftStickX, 0.6), stick(GamepadAxisType::LeftStickY, -0.3)]);
+
+    // Scaled so the far edge of the dead zone reads as 0.
+    let movement = app.world.run_system_once(|actions: Actions| actions.movement());
+    let expected = Vec2::new(0.45, -0.15) / (1. - STICK_DEAD_ZONE);
+    assert!(movement.abs_diff_eq(expected, 1e-6), "{:?}", movement);
+
+    // Only pushed past the threshold counts as held, e.g. for menus.
+    let (right, down) = app.world.run_system_once(|actions: Actions| (actions.pressed(Action::MoveRight), actions.pressed(Action::MoveDown)));
+    assert!(right);
+    assert!(!down);
+  }
+
+  #[test]
+  fn diagonals_are_no_faster() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+    send(&mut app, [stick(GamepadAxisType::LeftStickX, 1.), stick(GamepadAxisType::LeftStickY, 1.)]);
+
+    let movement = app.world.run_system_once(|actions: Actions| actions.movement());
+    assert!((movement.length() - 1.).abs() < 1e-6);
+  }
+
+  #[test]
+  fn stick_drift_inside_the_dead_zone_is_ignored() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+    send(&mut app, [stick(GamepadAxisType::LeftStickX, 0.12), stick(GamepadAxisType::LeftStickY, -0.1)]);
+
+    let (movement, strength) = app.world.run_system_once(|actions: Actions| (actions.movement(), actions.strength(Action::MoveRight)));
+    assert_eq!(movement, Vec2::ZERO);
+    assert_eq!(strength, 0.);
+  }
+
+  #[test]
+  fn buttons_are_just_pressed_for_one_frame() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+
+    send(&mut app, [button(GamepadButtonType::South, 1.)]);
+    let (just_pressed, pressed) = app.world.run_system_once(|actions: Actions| (actions.just_pressed(Action::Attack), actions.pressed(Action::Attack)));
+    assert!(just_pressed);
+    assert!(pressed);
+
+    send(&mut app, []);
+    let (just_pressed, pressed) = app.world.run_system_once(|actions: Actions| (actions.just_pressed(Action::Attack), actions.pressed(Action::Attack)));
+    assert!(!just_pressed);
+    assert!(pressed);
+
+    send(&mut app, [button(GamepadButtonType::South, 0.)]);
+    assert!(!app.world.run_system_once(|actions: Actions| actions.pressed(Action::Attack)));
+  }
+
+  #[test]
+  fn disconnecting_mid_press_lets_go_and_pauses() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+    send(&mut app, [button(GamepadButtonType::South, 1.), stick(GamepadAxisType::LeftStickX, 1.)]);
+
+    send(&mut app, [connection(GamepadConnection::Disconnected)]);
+    let (attack, movement) = app.world.run_system_once(|actions: Actions| (actions.pressed(Action::Attack), actions.movement()));
+    assert!(!attack);
+    assert_eq!(movement, Vec2::ZERO);
+
+    app.update();
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Paused);
+  }
+
+  #[test]
+  fn replugging_picks_up_where_it_left_off() {
+    let mut app = app();
+    send(&mut app, [connected()]);
+    send(&mut app, [connection(GamepadConnection::Disconnected)]);
+    send(&mut app, [connected()]);
+    send(&mut app, [button(GamepadButtonType::South, 1.)]);
+
+    assert!(app.world.run_system_once(|actions: Actions| actions.just_pressed(Action::Attack)));
+  }
+}
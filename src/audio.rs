@@ -1,7 +1,7 @@
-use bevy::prelude::*;
+use bevy::{audio::Volume, prelude::*};
 use rand::prelude::*;
 
-use crate::{events::{AxolBiteEvent, AxolDeath, PlayerDeathEvent, StartGameEvent, SwordHitEvent, SwordMissEvent}, game::InGameSet};
+use crate::{events::{AnimationFrameEvent, AxolBiteEvent, AxolDeath, BossPhaseEvent, PlayerDeathEvent, SpitEvent, SpitterDeath, StartGameEvent, SwordHitEvent, SwordMissEvent}, game::InGameSet, player::Player, rng::GameRng};
 pub struct GameAudioPlugin;
 
 impl Plugin for GameAudioPlugin {
@@ -14,6 +14,10 @@ impl Plugin for GameAudioPlugin {
           play_sword_miss_sound,
           play_axol_bite_sound,
           play_axol_died_sound,
+          play_spit_sound,
+          play_spitter_died_sound,
+          play_boss_roar_sound,
+          play_footstep_sound,
           play_player_died_sound
         ).in_set(InGameSet::PlayAudio));
   }
@@ -29,6 +33,10 @@ pub struct AudioHandles {
   pub sword_miss_3 : Handle<AudioSource>,
   pub axol_bite : Handle<AudioSource>,
   pub axol_death : Handle<AudioSource>,
+  pub spit : Handle<AudioSource>,
+  pub spitter_death : Handle<AudioSource>,
+  pub boss_roar : Handle<AudioSource>,
+  pub footstep : Handle<AudioSource>,
   pub player_death: Handle<AudioSource>,
   pub cave_theme_1: Handle<AudioSource>
 }
@@ -46,6 +54,10 @@ impl FromWorld for AudioHandles {
       sword_miss_3 : assets.load( "audio/sfx/27_sword_miss_3.wav"),
       axol_bite : assets.load("audio/sfx/07_landing_on_grass_1.wav"),
       axol_death : assets.load("audio/sfx/24_orc_death_spin.wav"),
+      spit : assets.load("audio/sfx/spit.wav"),
+      spitter_death : assets.load("audio/sfx/spitter_death.wav"),
+      boss_roar : assets.load("audio/sfx/boss_roar.wav"),
+      footstep : assets.load("audio/sfx/footstep.wav"),
       player_death: assets.load("audio/sfx/14_human_death_spin.wav"),
       cave_theme_1: assets.load("audio/music/cave_theme_1.wav")
     }
@@ -61,6 +73,15 @@ pub struct BiteAudio;
 #[derive(Component)]
 pub struct DeathAudio;
 
+#[derive(Component)]
+pub struct SpitAudio;
+
+#[derive(Component)]
+pub struct FootstepAudio;
+
+// Under everything else going on.
+const FOOTSTEP_VOLUME: f32 = 0.3;
+
 #[derive(Component)]
 pub struct Music;
 
@@ -91,6 +112,7 @@ pub fn play_sword_hit_sound(
   mut commands: Commands,
   handle: Res<AudioHandles>,
   mut event: EventReader<SwordHitEvent>,
+  mut rng: ResMut<GameRng>,
   exists: Query<Entity, With<SwordAudio>>
 ) {
   // Only play 1 sword audio at a time.
@@ -98,16 +120,15 @@ pub fn play_sword_hit_sound(
     return;
   }
 
-  let mut rng = rand::thread_rng();
-  let random_index = rng.gen_range(0..3);
-  let selected_audio_handle = match random_index {
-      0 => handle.sword_hit_1.clone(),
-      1 => handle.sword_hit_2.clone(),
-      2 => handle.sword_hit_3.clone(),
-      _ => panic!("Invalid random index"),
-  };
-
   if event.read().next().is_some() {
+    let random_index = rng.cosmetic.gen_range(0..3);
+    let selected_audio_handle = match random_index {
+        0 => handle.sword_hit_1.clone(),
+        1 => handle.sword_hit_2.clone(),
+        2 => handle.sword_hit_3.clone(),
+        _ => panic!("Invalid random index"),
+    };
+
     commands.spawn((
         SwordAudio,
         AudioBundle {
@@ -122,6 +143,7 @@ pub fn play_sword_miss_sound(
   mut commands: Commands,
   handle: Res<AudioHandles>,
   mut event: EventReader<SwordMissEvent>,
+  mut rng: ResMut<GameRng>,
   exists: Query<Entity, With<SwordAudio>>
 ) {
   // Only play 1 sword audio at a time.
@@ -129,16 +151,15 @@ pub fn play_sword_miss_sound(
     return;
   }
 
-  let mut rng = rand::thread_rng();
-  let random_index = rng.gen_range(0..3);
-  let selected_audio_handle = match random_index {
-      0 => handle.sword_miss_1.clone(),
-      1 => handle.sword_miss_2.clone(),
-      2 => handle.sword_miss_3.clone(),
-      _ => panic!("Invalid random index"),
-  };
-
   if event.read().next().is_some() {
+    let random_index = rng.cosmetic.gen_range(0..3);
+    let selected_audio_handle = match random_index {
+        0 => handle.sword_miss_1.clone(),
+        1 => handle.sword_miss_2.clone(),
+        2 => handle.sword_miss_3.clone(),
+        _ => panic!("Invalid random index"),
+    };
+
     commands.spawn((
         SwordAudio,
         AudioBundle {
@@ -193,6 +214,86 @@ pub fn play_axol_died_sound(
   }
 }
 
+pub fn play_spit_sound(
+  mut commands: Commands,
+  handle: Res<AudioHandles>,
+  mut event: EventReader<SpitEvent>,
+  exists: Query<Entity, With<SpitAudio>>
+) {
+  // Only play 1 spit audio at a time.
+  if exists.iter().next().is_some() {
+    return;
+  }
+
+  if event.read().next().is_some() {
+    commands.spawn((
+        SpitAudio,
+        AudioBundle {
+            source: handle.spit.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        },
+    ));
+  }
+}
+
+pub fn play_spitter_died_sound(
+  mut commands: Commands,
+  handle: Res<AudioHandles>,
+  mut event: EventReader<SpitterDeath>,
+  exists: Query<Entity, With<DeathAudio>>
+) {
+  // Only play 1 death audio at a time.
+  if exists.iter().next().is_some() {
+    return;
+  }
+
+  if event.read().next().is_some() {
+    commands.spawn((
+        DeathAudio,
+        AudioBundle {
+            source: handle.spitter_death.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        },
+    ));
+  }
+}
+
+pub fn play_boss_roar_sound(
+  mut commands: Commands,
+  handle: Res<AudioHandles>,
+  mut event: EventReader<BossPhaseEvent>,
+) {
+  if event.read().next().is_some() {
+    commands.spawn(AudioBundle {
+      source: handle.boss_roar.clone(),
+      settings: PlaybackSettings::DESPAWN,
+    });
+  }
+}
+
+// On frames whose animation sends "footstep".
+pub fn play_footstep_sound(
+  mut commands: Commands,
+  handle: Res<AudioHandles>,
+  mut event: EventReader<AnimationFrameEvent>,
+  player: Query<(), With<Player>>,
+  exists: Query<Entity, With<FootstepAudio>>
+) {
+  // Only the player's steps are heard over the din of the horde.
+  let stepped = event.read().any(|frame| frame.name == "footstep" && player.contains(frame.entity));
+
+  // Only play 1 footstep audio at a time.
+  if stepped && exists.iter().next().is_none() {
+    commands.spawn((
+        FootstepAudio,
+        AudioBundle {
+            source: handle.footstep.clone(),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(FOOTSTEP_VOLUME)),
+        },
+    ));
+  }
+}
+
 pub fn play_player_died_sound(
   mut commands: Commands,
   handle: Res<AudioHandles>,
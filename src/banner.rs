@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::{actions::{Action, InputBindings}, game::{Game, GameState}, score::Score, splash::despawn_screen};
+
+const BANNER_FONT_SIZE: f32 = 80.0;
+const BANNER_TEXT_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+pub struct BannerPlugin;
+
+impl Plugin for BannerPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(GameState::LevelTransition), level_banner_setup)
+       .add_systems(OnExit(GameState::LevelTransition), despawn_screen::<OnBanner>)
+       .add_systems(OnEnter(GameState::Victory), victory_banner_setup)
+       .add_systems(OnExit(GameState::Victory), despawn_screen::<OnBanner>)
+       .add_systems(OnEnter(GameState::Paused), pause_banner_setup)
+       .add_systems(OnExit(GameState::Paused), despawn_screen::<OnBanner>);
+  }
+}
+
+// Tag component used to tag the text shown between levels, on victory and while paused
+#[derive(Component)]
+struct OnBanner;
+
+fn spawn_banner(commands: &mut Commands, text: String) {
+  commands
+      .spawn((
+          NodeBundle {
+              style: Style {
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  width: Val::Percent(100.0),
+                  height: Val::Percent(100.0),
+                  ..default()
+              },
+              ..default()
+          },
+          OnBanner,
+      ))
+      .with_children(|parent| {
+          parent.spawn(
+              TextBundle::from_section(
+                  text,
+                  TextStyle {
+                      font_size: BANNER_FONT_SIZE,
+                      color: BANNER_TEXT_COLOR,
+                      ..default()
+                  },
+              )
+              .with_text_alignment(TextAlignment::Center),
+          );
+      });
+}
+
+fn level_banner_setup(mut commands: Commands, game: Res<Game>) {
+  spawn_banner(&mut commands, format!("Level {}", game.level() + 1));
+}
+
+fn pause_banner_setup(mut commands: Commands, bindings: Res<InputBindings>) {
+  spawn_banner(&mut commands, format!("Paused\nPress {} to resume", bindings.describe(Action::Pause)));
+}
+
+fn victory_banner_setup(mut commands: Commands, score: Res<Score>, bindings: Res<InputBindings>) {
+  spawn_banner(&mut commands, format!("Victory!\nScore: {}\nPress {} to keep going", **score, bindings.describe(Action::Start)));
+}
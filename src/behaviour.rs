@@ -0,0 +1,425 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::{collections::HashSet, f32::consts::TAU};
+
+use crate::{axol::Axol, combat::{check_axol_attack, Health}, game::InGameSet, movement::{Facing, MoveIntent}, pathfinding::{to_grid_coords, to_translation, FlowField, NavPath, Navigation}, player::Player, rng::GameRng, sprite::AnimState};
+
+// Close enough to a wander target to stop there.
+const WANDER_ARRIVAL: f32 = 4.;
+
+const LABEL_FONT_SIZE: f32 = 10.;
+// Above the sprite it names.
+const LABEL_OFFSET: Vec3 = Vec3::new(0., 32., 1.);
+
+// Decides what each enemy is up to and steers it accordingly.
+pub struct BehaviourPlugin;
+
+impl Plugin for BehaviourPlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(FixedUpdate, (update_ai_states, steer_enemies).chain().before(check_axol_attack).in_set(InGameSet::Combat));
+  }
+}
+
+// F3 shows every enemy's state, home area and ranges.
+pub struct BehaviourDebugPlugin;
+
+impl Plugin for BehaviourDebugPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_resource::<ShowBehaviourDebug>()
+         .add_systems(Update, (toggle_behaviour_debug, draw_behaviour_debug, label_ai_states).chain());
+  }
+}
+
+// How a kind of enemy behaves. Distances in pixels, times in seconds.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Behaviour {
+  // Wanders within this of where it spawned until it spots the player.
+  pub home_radius: f32,
+  // Time between picking somewhere new to wander to.
+  pub wander_interval: f32,
+  // Spots the player this close.
+  pub detection_radius: f32,
+  // Time spent staring at the player before giving chase.
+  pub aggro_time: f32,
+  // Gives up the chase and heads home when the player gets this far away.
+  pub lose_radius: f32,
+  // Stops to attack this close.
+  pub attack_range: f32,
+  // Backs away while attacking when the player is closer than this. 0 stands
+  // its ground.
+  #[serde(default)]
+  pub keep_distance: f32,
+  // Backs off once, when health falls to this fraction of its maximum. 0
+  // fights to the death.
+  pub retreat_health: f32,
+  // Time spent backing off before coming back for more.
+  pub retreat_time: f32,
+}
+
+// Enemies without one go straight for the player, as axols always used to.
+impl Default for Behaviour {
+  fn default() -> Self {
+    Behaviour {
+      home_radius: 0.,
+      wander_interval: 1.,
+      detection_radius: f32::INFINITY,
+      aggro_time: 0.,
+      lose_radius: f32::INFINITY,
+      attack_range: 70.,
+      keep_distance: 0.,
+      retreat_health: 0.,
+      retreat_time: 0.,
+    }
+  }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiState {
+  #[default]
+  Wander,
+  Aggro,
+  Chase,
+  Attack,
+  Retreat,
+}
+
+#[derive(Default, Component)]
+pub struct Ai {
+  pub state: AiState,
+  pub home: Vec2,
+  pub behaviour: Behaviour,
+  wander_target: Vec2,
+  // Runs out when it's time to leave the current state, or to wander
+  // somewhere else.
+  timer: Timer,
+  retreated: bool,
+}
+
+impl Ai {
+  pub fn new(home: Vec2, behaviour: Behaviour) -> Self {
+    Ai {
+      state: AiState::Wander,
+      home,
+      behaviour,
+      wander_target: home,
+      timer: Timer::from_seconds(behaviour.wander_interval, TimerMode::Once),
+      retreated: false,
+    }
+  }
+
+  fn enter(&mut self, state: AiState) {
+    let duration = match state {
+      AiState::Wander => {
+        self.wander_target = self.home;
+        self.behaviour.wander_interval
+      },
+      AiState::Aggro => self.behaviour.aggro_time,
+      AiState::Retreat => {
+        self.retreated = true;
+        self.behaviour.retreat_time
+      },
+      AiState::Chase | AiState::Attack => 0.,
+    };
+
+    self.state = state;
+    self.timer = Timer::from_seconds(duration, TimerMode::Once);
+  }
+
+  // Where the state machine goes from here, if anywhere.
+  fn next_state(&self, distance: f32, low_health: bool, player_alive: bool, timer_finished: bool) -> Option<AiState> {
+    let behaviour = &self.behaviour;
+    let engaged = matches!(self.state, AiState::Aggro | AiState::Chase | AiState::Attack);
+
+    if !player_alive {
+      return (self.state != AiState::Wander).then_some(AiState::Wander);
+    }
+
+    if engaged && low_health && !self.retreated {
+      return Some(AiState::Retreat);
+    }
+
+    match self.state {
+      AiState::Wander if distance <= behaviour.detection_radius => Some(AiState::Aggro),
+      AiState::Aggro if distance > behaviour.detection_radius => Some(AiState::Wander),
+      AiState::Aggro if timer_finished => Some(AiState::Chase),
+      AiState::Chase if distance > behaviour.lose_radius => Some(AiState::Wander),
+      AiState::Chase if distance <= behaviour.attack_range => Some(AiState::Attack),
+      AiState::Attack if distance > behaviour.attack_range => Some(AiState::Chase),
+      AiState::Retreat if timer_finished => Some(AiState::Chase),
+      _ => None,
+    }
+  }
+}
+
+fn update_ai_states(
+  time: Res<Time>,
+  mut rng: ResMut<GameRng>,
+  player: Query<(&Transform, &Health), With<Player>>,
+  mut enemies: Query<(&Transform, &Health, &mut Ai), With<Axol>>,
+) {
+  let Ok((player_transform, player_health)) = player.get_single() else {
+    return;
+  };
+  let player_position = player_transform.translation.xy();
+
+  for (transform, health, mut ai) in enemies.iter_mut() {
+    if health.0 <= 0 {
+      continue;
+    }
+
+    let distance = transform.translation.xy().distance(player_position);
+    let low_health = (health.0 as f32) <= ai.behaviour.retreat_health * health.1 as f32;
+    let timer_finished = ai.timer.tick(time.delta()).just_finished();
+
+    if let Some(state) = ai.next_state(distance, low_health, player_health.0 > 0, timer_finished) {
+      ai.enter(state);
+    } else if ai.state == AiState::Wander && timer_finished {
+      // Somewhere at random in the home area.
+      let angle = rng.gameplay.gen_range(0.0..TAU);
+      let reach = ai.behaviour.home_radius * rng.gameplay.gen::<f32>().sqrt();
+
+      ai.wander_target = ai.home + Vec2::from_angle(angle) * reach;
+      ai.timer.reset();
+    }
+  }
+}
+
+pub fn steer_enemies(
+  navigation: Res<Navigation>,
+  flow_field: Res<FlowField>,
+  player: Query<&Transform, With<Player>>,
+  mut enemies: Query<(&Transform, &Ai, &Health, &mut MoveIntent, &mut Facing, &mut NavPath, &mut AnimState), With<Axol>>,
+) {
+  let Ok(player_transform) = player.get_single() else {
+    return;
+  };
+  let player_position = player_transform.translation.xy();
+
+  for (transform, ai, health, mut intent, mut facing, mut path, mut anim_state) in enemies.iter_mut() {
+    let position = transform.translation.xy();
+
+    if health.0 <= 0 {
+      intent.direction = Vec2::ZERO;
+      continue;
+    }
+
+    let direction = match ai.state {
+      AiState::Wander => {
+        let offset = ai.wander_target - position;
+        if offset.length() > WANDER_ARRIVAL { offset.normalize() } else { Vec2::ZERO }
+      },
+      AiState::Chase => {
+        // Follow the path around anything in the way, or head straight for
+        // the player once in the same cell or when there's no path.
+        let cell = to_grid_coords(position);
+        let next_cell = match *navigation {
+          Navigation::Paths => path.next_waypoint(cell),
+          Navigation::FlowField => flow_field.next_cell(cell),
+        };
+
+        let target = next_cell.map_or(player_position, to_translation);
+        (target - position).normalize_or_zero()
+      },
+      AiState::Retreat => (position - player_position).normalize_or_zero(),
+      AiState::Attack if position.distance(player_position) < ai.behaviour.keep_distance => {
+        (position - player_position).normalize_or_zero()
+      },
+      AiState::Aggro | AiState::Attack => {
+        if let Some(toward) = (player_position - position).try_normalize() {
+          facing.0 = toward;
+        }
+        Vec2::ZERO
+      },
+    };
+
+    intent.direction = direction;
+
+    // Attacks play out before going back to walking or standing.
+    match *anim_state {
+      AnimState::Idle | AnimState::IdleInjured if direction != Vec2::ZERO => *anim_state = AnimState::Walk,
+      AnimState::Walk if direction == Vec2::ZERO => {
+        *anim_state = if health.0 < health.1 as i32 { AnimState::IdleInjured } else { AnimState::Idle };
+      },
+      _ => (),
+    }
+  }
+}
+
+#[derive(Default, Resource)]
+struct ShowBehaviourDebug(bool);
+
+fn state_color(state: AiState) -> Color {
+  match state {
+    AiState::Wander => Color::GREEN,
+    AiState::Aggro => Color::YELLOW,
+    AiState::Chase => Color::ORANGE,
+    AiState::Attack => Color::RED,
+    AiState::Retreat => Color::CYAN,
+  }
+}
+
+fn toggle_behaviour_debug(
+  keyboard_input: Res<Input<KeyCode>>,
+  mut show: ResMut<ShowBehaviourDebug>,
+) {
+  if keyboard_input.just_pressed(KeyCode::F3) {
+    show.0 = !show.0;
+  }
+}
+
+fn draw_behaviour_debug(
+  show: Res<ShowBehaviourDebug>,
+  mut gizmos: Gizmos,
+  enemies: Query<(&Transform, &Ai)>,
+) {
+  if !show.0 {
+    return;
+  }
+
+  for (transform, ai) in enemies.iter() {
+    let position = transform.translation.xy();
+    let color = state_color(ai.state);
+
+    gizmos.circle_2d(ai.home, ai.behaviour.home_radius, Color::GRAY);
+    gizmos.circle_2d(position, ai.behaviour.attack_range, color);
+
+    if ai.behaviour.keep_distance > 0. {
+      gizmos.circle_2d(position, ai.behaviour.keep_distance, color);
+    }
+
+    if ai.behaviour.detection_radius.is_finite() {
+      gizmos.circle_2d(position, ai.behaviour.detection_radius, color);
+    }
+
+    if ai.state == AiState::Wander {
+      gizmos.line_2d(position, ai.wander_target, color);
+    }
+  }
+}
+
+// Names the state of the enemy it follows around.
+#[derive(Component)]
+struct StateLabel(Entity);
+
+fn label_ai_states(
+  mut commands: Commands,
+  show: Res<ShowBehaviourDebug>,
+  enemies: Query<(Entity, &Transform, &Ai)>,
+  mut labels: Query<(Entity, &StateLabel, &mut Text, &mut Transform), Without<Ai>>,
+) {
+  let mut labelled = HashSet::new();
+
+  for (label_entity, label, mut text, mut transform) in labels.iter_mut() {
+    match enemies.get(label.0) {
+      Ok((_, enemy_transform, ai)) if show.0 => {
+        text.sections[0].value = format!("{:?}", ai.state);
+        text.sections[0].style.color = state_color(ai.state);
+        transform.translation = enemy_transform.translation + LABEL_OFFSET;
+        labelled.insert(label.0);
+      },
+      _ => commands.entity(label_entity).despawn(),
+    }
+  }
+
+  if !show.0 {
+    return;
+  }
+
+  for (entity, transform, ai) in enemies.iter().filter(|(entity, _, _)| !labelled.contains(entity)) {
+    commands.spawn((
+      StateLabel(entity),
+      Text2dBundle {
+        text: Text::from_section(format!("{:?}", ai.state), TextStyle {
+          font_size: LABEL_FONT_SIZE,
+          color: state_color(ai.state),
+          ..default()
+        }),
+        transform: Transform::from_translation(transform.translation + LABEL_OFFSET),
+        ..default()
+      },
+    ));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use AiState::*;
+
+  const DETECTION: f32 = 200.;
+  const LOSE: f32 = 400.;
+  const ATTACK: f32 = 70.;
+
+  fn ai(state: AiState, retreated: bool) -> Ai {
+    let mut ai = Ai::new(Vec2::ZERO, Behaviour {
+      detection_radius: DETECTION,
+      lose_radius: LOSE,
+      attack_range: ATTACK,
+      retreat_health: 0.3,
+      ..default()
+    });
+    ai.state = state;
+    ai.retreated = retreated;
+    ai
+  }
+
+  #[test]
+  fn states_change_at_their_thresholds() {
+    // (state, retreated already, distance, low health, player alive, timer finished, next state)
+    let table = [
+      // Spots the player only within the detection radius.
+      (Wander, false, DETECTION, false, true, false, Some(Aggro)),
+      (Wander, false, DETECTION + 1., false, true, false, None),
+      (Wander, false, DETECTION + 1., false, true, true, None),
+      // Stares until the timer runs out, unless the player slips away first.
+      (Aggro, false, DETECTION, false, true, false, None),
+      (Aggro, false, DETECTION, false, true, true, Some(Chase)),
+      (Aggro, false, DETECTION + 1., false, true, true, Some(Wander)),
+      // Chases until in range, or until the player is lost.
+      (Chase, false, ATTACK + 1., false, true, false, None),
+      (Chase, false, ATTACK, false, true, false, Some(Attack)),
+      (Chase, false, LOSE, false, true, false, None),
+      (Chase, false, LOSE + 1., false, true, false, Some(Wander)),
+      // Attacks until the player steps out of range.
+      (Attack, false, ATTACK, false, true, false, None),
+      (Attack, false, ATTACK + 1., false, true, false, Some(Chase)),
+      // Backs off once when hurt while engaged, never while wandering.
+      (Wander, false, DETECTION, true, true, false, Some(Aggro)),
+      (Aggro, false, DETECTION, true, true, false, Some(Retreat)),
+      (Chase, false, ATTACK + 1., true, true, false, Some(Retreat)),
+      (Attack, false, ATTACK, true, true, false, Some(Retreat)),
+      (Attack, true, ATTACK, true, true, false, None),
+      // Comes back for more when the retreat is over.
+      (Retreat, true, ATTACK, true, true, false, None),
+      (Retreat, true, ATTACK, true, true, true, Some(Chase)),
+      // Everything goes back to wandering once the player is dead.
+      (Wander, false, 0., false, false, true, None),
+      (Aggro, false, 0., false, false, false, Some(Wander)),
+      (Chase, false, 0., true, false, false, Some(Wander)),
+      (Attack, false, 0., false, false, false, Some(Wander)),
+      (Retreat, true, 0., false, false, false, Some(Wander)),
+    ];
+
+    for (state, retreated, distance, low_health, player_alive, timer_finished, expected) in table {
+      assert_eq!(
+        ai(state, retreated).next_state(distance, low_health, player_alive, timer_finished),
+        expected,
+        "{:?} at {} (retreated {}, low health {}, player alive {}, timer finished {})",
+        state, distance, retreated, low_health, player_alive, timer_finished,
+      );
+    }
+  }
+
+  #[test]
+  fn retreating_uses_up_the_one_retreat() {
+    let mut ai = ai(Attack, false);
+
+    ai.enter(Retreat);
+    assert_eq!(ai.next_state(ATTACK, true, true, true), Some(Chase));
+
+    // Still hurt, but it fights on this time.
+    ai.enter(Chase);
+    assert_eq!(ai.next_state(ATTACK + 1., true, true, false), None);
+  }
+}
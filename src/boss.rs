@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{behaviour::{Ai, Behaviour}, combat::{check_axol_attack, check_spitter_attack, AttackCooldown, Health, RangedAttack}, events::BossPhaseEvent, game::InGameSet, movement::MoveSpeed};
+
+const BAR_WIDTH: f32 = 50.;
+const BAR_HEIGHT: Val = Val::Px(16.);
+const BAR_BOTTOM: Val = Val::Px(24.);
+const BAR_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.6);
+const BAR_FILL: Color = Color::rgb(0.8, 0.1, 0.2);
+const NAME_FONT_SIZE: f32 = 24.;
+const NAME_COLOR: Color = Color::WHITE;
+
+// Moves bosses on to their next phase as their health falls.
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(FixedUpdate, advance_boss_phases.before(check_axol_attack).before(check_spitter_attack).in_set(InGameSet::Combat));
+  }
+}
+
+// Shows the health of the boss in play along the bottom of the screen.
+pub struct BossBarPlugin;
+
+impl Plugin for BossBarPlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(Startup, setup_boss_bar)
+         .add_systems(Update, update_boss_bar);
+  }
+}
+
+// What makes an enemy in the table a boss.
+#[derive(Deserialize, Clone)]
+pub struct BossDefinition {
+  // Shown above its health bar.
+  pub name: String,
+  // Drawn this much bigger than its sprite sheet.
+  pub scale: f32,
+  // In the order they start. Until the first, it fights like any other enemy
+  // of its kind.
+  #[serde(default)]
+  pub phases: Vec<BossPhase>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BossPhase {
+  // Starts once health falls to this fraction of its maximum.
+  pub health: f32,
+  // Multiplies the speed it spawned with.
+  pub speed: f32,
+  // Seconds between attacks.
+  pub cooldown: f32,
+  #[serde(default)]
+  pub behaviour: Behaviour,
+  // Spits from then on, or goes back to biting when None.
+  #[serde(default)]
+  pub ranged: Option<RangedAttack>,
+}
+
+#[derive(Component)]
+pub struct Boss {
+  pub name: String,
+  phases: Vec<BossPhase>,
+  // How many phases have started.
+  phase: usize,
+  spawn_speed: f32,
+}
+
+impl Boss {
+  pub fn new(definition: &BossDefinition, speed: f32) -> Self {
+    Boss {
+      name: definition.name.clone(),
+      phases: definition.phases.clone(),
+      phase: 0,
+      spawn_speed: speed,
+    }
+  }
+}
+
+// Several phases can start at once after a big hit, or when a boss comes back
+// from a save, but only the last one takes effect.
+fn advance_boss_phases(
+  mut commands: Commands,
+  mut bosses: Query<(Entity, &mut Boss, &Health, &mut Ai, &mut MoveSpeed, &mut AttackCooldown)>,
+  mut phase_event: EventWriter<BossPhaseEvent>,
+) {
+  for (entity, mut boss, health, mut ai, mut speed, mut cooldown) in bosses.iter_mut() {
+    if health.0 <= 0 {
+      continue;
+    }
+
+    let fraction = health.0 as f32 / health.1.max(1) as f32;
+    let mut started = None;
+
+    while let Some(phase) = boss.phases.get(boss.phase).filter(|phase| fraction <= phase.health) {
+      started = Some(phase.clone());
+      boss.phase += 1;
+    }
+
+    let Some(phase) = started else {
+      continue;
+    };
+
+    ai.behaviour = phase.behaviour;
+    speed.walk = boss.spawn_speed * phase.speed;
+    speed.run = speed.walk;
+    **cooldown = Timer::from_seconds(phase.cooldown, TimerMode::Repeating);
+
+    match phase.ranged {
+      Some(ranged) => commands.entity(entity).insert(ranged),
+      None => commands.entity(entity).remove::<RangedAttack>(),
+    };
+
+    phase_event.send_default();
+  }
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarName;
+
+#[derive(Component)]
+struct BossBarFill;
+
+fn setup_boss_bar(
+  mut commands: Commands
+) {
+  commands.spawn((
+    BossBar,
+    NodeBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        bottom: BAR_BOTTOM,
+        left: Val::Percent((100. - BAR_WIDTH) / 2.),
+        width: Val::Percent(BAR_WIDTH),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+      },
+      visibility: Visibility::Hidden,
+      ..default()
+    },
+  )).with_children(|bar| {
+    bar.spawn((
+      BossBarName,
+      TextBundle::from_section("", TextStyle {
+        font_size: NAME_FONT_SIZE,
+        color: NAME_COLOR,
+        ..default()
+      }),
+    ));
+
+    bar.spawn(NodeBundle {
+      style: Style {
+        width: Val::Percent(100.),
+        height: BAR_HEIGHT,
+        ..default()
+      },
+      background_color: BAR_BACKGROUND.into(),
+      ..default()
+    }).with_children(|background| {
+      background.spawn((
+        BossBarFill,
+        NodeBundle {
+          style: Style {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            ..default()
+          },
+          background_color: BAR_FILL.into(),
+          ..default()
+        },
+      ));
+    });
+  });
+}
+
+fn update_boss_bar(
+  bosses: Query<(&Boss, &Health)>,
+  mut bar: Query<&mut Visibility, With<BossBar>>,
+  mut name: Query<&mut Text, With<BossBarName>>,
+  mut fill: Query<&mut Style, With<BossBarFill>>,
+) {
+  let Ok(mut visibility) = bar.get_single_mut() else {
+    return;
+  };
+
+  let Some((boss, health)) = bosses.iter().find(|(_, health)| health.0 > 0) else {
+    *visibility = Visibility::Hidden;
+    return;
+  };
+
+  *visibility = Visibility::Inherited;
+
+  if let Ok(mut text) = name.get_single_mut() {
+    text.sections[0].value.clone_from(&boss.name);
+  }
+  if let Ok(mut style) = fill.get_single_mut() {
+    style.width = Val::Percent(100. * health.0 as f32 / health.1.max(1) as f32);
+  }
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+use serde::Deserialize;
+
+use crate::{axol::Axol, events::NewWaveEvent, game::{Game, GameState, InGameSet, RequiredAssets}, movement::MoveIntent, player::Player, ron_asset::{RonAsset, RonAssetLoader}, spawn_points::LevelSpawnPoints};
+
+// Seconds between clearing a level and the first wave of the next one.
+const LEVEL_TRANSITION_TIME: f32 = 3.;
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_asset::<Campaign>()
+         .init_asset_loader::<RonAssetLoader<Campaign>>()
+         .init_resource::<CampaignLibrary>()
+         .init_resource::<TransitionTimer>()
+         .add_systems(OnEnter(GameState::Splash), return_to_first_level)
+         .add_systems(OnEnter(GameState::LevelTransition), start_level_transition)
+         .add_systems(FixedUpdate, (finish_level_transition).in_set(InGameSet::Transition));
+  }
+}
+
+// The LDtk levels played in order, loaded from assets/campaign.campaign.ron.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct Campaign {
+  pub levels: Vec<CampaignLevel>,
+}
+
+impl RonAsset for Campaign {
+  const EXTENSIONS: &'static [&'static str] = &["campaign.ron"];
+}
+
+#[derive(Deserialize)]
+pub struct CampaignLevel {
+  // Identifier of the level in axol.ldtk.
+  pub level: String,
+  // Waves to clear before moving on.
+  pub waves: u8,
+}
+
+impl Campaign {
+  // The level a wave is played on, None once the last level is cleared.
+  pub fn level_for_wave(&self, wave_number: u8) -> Option<usize> {
+    let mut last_wave = 0;
+
+    for (index, level) in self.levels.iter().enumerate() {
+      last_wave += level.waves as u32;
+      if (wave_number as u32) < last_wave {
+        return Some(index);
+      }
+    }
+
+    None
+  }
+
+  // Clearing this wave wins the campaign.
+  pub fn is_last_wave(&self, wave_number: u8) -> bool {
+    self.level_for_wave(wave_number).is_some() && self.level_for_wave(wave_number.saturating_add(1)).is_none()
+  }
+
+  pub fn selection(&self, level: usize) -> LevelSelection {
+    LevelSelection::Identifier(self.levels[level].level.clone())
+  }
+}
+
+#[derive(Resource)]
+pub struct CampaignLibrary {
+  pub campaign: Handle<Campaign>,
+}
+
+impl FromWorld for CampaignLibrary {
+  fn from_world(world: &mut World) -> Self {
+    let library = CampaignLibrary {
+      campaign: world.resource::<AssetServer>().load("campaign.campaign.ron"),
+    };
+
+    world.get_resource_or_insert_with(RequiredAssets::default).add(&library.campaign);
+
+    library
+  }
+}
+
+#[derive(Default, Resource, Deref, DerefMut)]
+struct TransitionTimer(Timer);
+
+// Every run starts on the first level, which spawns behind the splash screen.
+fn return_to_first_level(
+  mut commands: Commands,
+  selection: Option<Res<LevelSelection>>,
+  campaigns: Res<Assets<Campaign>>,
+  library: Res<CampaignLibrary>,
+) {
+  let campaign = campaigns.get(&library.campaign).expect("Campaign not loaded");
+  let first_level = campaign.selection(0);
+
+  // Only touch the selection when it changes so the level isn't respawned.
+  if selection.as_deref() != Some(&first_level) {
+    commands.insert_resource(first_level);
+  }
+}
+
+fn start_level_transition(
+  mut commands: Commands,
+  mut selection: ResMut<LevelSelection>,
+  mut timer: ResMut<TransitionTimer>,
+  campaigns: Res<Assets<Campaign>>,
+  library: Res<CampaignLibrary>,
+  game: Res<Game>,
+  axols: Query<Entity, With<Axol>>,
+) {
+  let campaign = campaigns.get(&library.campaign).expect("Campaign not loaded");
+
+  *selection = campaign.selection(game.level());
+
+  for entity in axols.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+
+  *timer = TransitionTimer(Timer::from_seconds(LEVEL_TRANSITION_TIME, TimerMode::Once));
+}
+
+fn finish_level_transition(
+  time: Res<Time>,
+  mut timer: ResMut<TransitionTimer>,
+  spawn_points: Res<LevelSpawnPoints>,
+  mut player: Query<(&mut Transform, &mut MoveIntent), With<Player>>,
+  mut new_wave: EventWriter<NewWaveEvent>,
+) {
+  if timer.tick(time.delta()).just_finished() {
+    let (mut transform, mut intent) = player.get_single_mut().expect("Player despawned");
+
+    transform.translation = spawn_points.player_start.extend(transform.translation.z);
+    *intent = MoveIntent::default();
+
+    new_wave.send_default();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn waves_are_played_on_the_levels_in_order() {
+    let campaign = Campaign {
+      levels: vec![
+        CampaignLevel { level: "Level_0".into(), waves: 2 },
+        CampaignLevel { level: "Level_1".into(), waves: 3 },
+      ],
+    };
+
+    let levels: Vec<_> = (0..6).map(|wave_number| campaign.level_for_wave(wave_number)).collect();
+
+    assert_eq!(levels, vec![Some(0), Some(0), Some(1), Some(1), Some(1), None]);
+  }
+
+  #[test]
+  fn only_the_last_wave_of_the_last_level_wins() {
+    let campaign = Campaign {
+      levels: vec![
+        CampaignLevel { level: "Level_0".into(), waves: 2 },
+        CampaignLevel { level: "Level_1".into(), waves: 3 },
+      ],
+    };
+
+    let winning: Vec<_> = (0..8).filter(|wave_number| campaign.is_last_wave(*wave_number)).collect();
+
+    assert_eq!(winning, vec![4]);
+  }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::Deserialize;
+
+use crate::nowalk::{LevelNoWalk, GRID_SIZE};
+
+// Keeps a box sitting exactly on a cell edge from counting as inside the cell.
+const EDGE_TOLERANCE: f32 = 0.01;
+
+// Box around an entity's transform that has to stay out of NoWalk cells.
+#[derive(Default, Component, Clone, Copy, Deserialize)]
+pub struct Collider {
+  pub half_extents: Vec2,
+  #[serde(default)]
+  pub offset: Vec2,
+}
+
+impl Collider {
+  pub fn new(width: f32, height: f32) -> Self {
+    Collider {
+      half_extents: Vec2::new(width, height) / 2.,
+      offset: Vec2::ZERO,
+    }
+  }
+
+  pub fn bounds(&self, position: Vec2) -> Rect {
+    Rect::from_center_half_size(position + self.offset, self.half_extents)
+  }
+}
+
+// Moves the box one axis at a time, stopping each axis at the first NoWalk
+// cell in the way, so blocked movement slides along walls rather than stopping.
+pub fn move_and_slide(level_nowalk: &LevelNoWalk, collider: &Collider, position: Vec2, motion: Vec2) -> Vec2 {
+  let mut position = position;
+
+  position.x += sweep(level_nowalk, collider.bounds(position), motion.x, false);
+  position.y += sweep(level_nowalk, collider.bounds(position), motion.y, true);
+
+  position
+}
+
+// How far the box can travel along one axis before it hits a NoWalk cell.
+// Cells the box already overlaps are ignored so an entity can always walk out.
+fn sweep(level_nowalk: &LevelNoWalk, bounds: Rect, distance: f32, vertical: bool) -> f32 {
+  if distance == 0. {
+    return 0.;
+  }
+
+  // Work in (along, across) the direction of travel.
+  let (min, max) = if vertical { (bounds.min.yx(), bounds.max.yx()) } else { (bounds.min, bounds.max) };
+  let size = GRID_SIZE as f32;
+
+  let first_across = ((min.y + EDGE_TOLERANCE) / size).floor() as i32;
+  let last_across = ((max.y - EDGE_TOLERANCE) / size).ceil() as i32 - 1;
+
+  let blocked = |along: i32| {
+    (first_across..=last_across).any(|across| {
+      let grid_coords = if vertical { GridCoords::new(across, along) } else { GridCoords::new(along, across) };
+      level_nowalk.in_nowalk(&grid_coords)
+    })
+  };
+
+  if distance > 0. {
+    let first = ((max.x - EDGE_TOLERANCE) / size).ceil() as i32;
+    let last = ((max.x + distance) / size).ceil() as i32 - 1;
+
+    if let Some(along) = (first..=last).find(|along| blocked(*along)) {
+      return (along as f32 * size - max.x).clamp(0., distance);
+    }
+  } else {
+    let first = ((min.x + EDGE_TOLERANCE) / size).floor() as i32 - 1;
+    let last = ((min.x + distance) / size).floor() as i32;
+
+    if let Some(along) = (last..=first).rev().find(|along| blocked(*along)) {
+      return ((along + 1) as f32 * size - min.x).clamp(distance, 0.);
+    }
+  }
+
+  distance
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn slides_along_a_wall_in_the_way() {
+    let level_nowalk = LevelNoWalk::new(4, 4, [GridCoords::new(2, 1)].into());
+    let collider = Collider::new(16., 16.);
+
+    let position = move_and_slide(&level_nowalk, &collider, Vec2::new(40., 48.), Vec2::new(40., 10.));
+
+    assert_eq!(position, Vec2::new(56., 58.));
+  }
+
+  #[test]
+  fn stops_at_the_edge_of_the_level() {
+    let level_nowalk = LevelNoWalk::new(4, 4, Default::default());
+    let collider = Collider::new(16., 16.);
+
+    let position = move_and_slide(&level_nowalk, &collider, Vec2::new(16., 16.), Vec2::new(-50., -4.));
+
+    assert_eq!(position, Vec2::new(8., 12.));
+  }
+}
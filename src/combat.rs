@@ -1,79 +1,173 @@
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 
-use crate::{axol::Axol, events::{AxolBiteEvent, AxolDeath, PlayerDeathEvent, SwordHitEvent, SwordMissEvent, SwordSwingEvent, WalkEvent}, game::InGameSet, player::Player, score::Score, sprite::{self, AnimFrame, AnimState, MoveDir}};
+use crate::{axol::{Axol, BiteDamage, ScoreValue}, behaviour::{Ai, AiState}, boss::Boss, collision::Collider, events::{AxolBiteEvent, AxolDeath, PlayerDeathEvent, ProjectileHitEvent, SpawnProjectileEvent, SpitEvent, SpitterDeath, SwordHitEvent, SwordMissEvent}, game::InGameSet, movement::{Facing, Separation}, player::Player, projectile::ProjectileStats, rng::GameRng, score::Score, sprite::{self, AnimFrame, AnimState, AnimationSet, MoveDir}};
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
   fn build(&self, app: &mut App) {
       app.add_systems(Update, (
-        check_player_attack,
         handle_player_damage,
-        check_axol_attack,
-        walk_to_player,
         handle_axol_damage,
+      ).in_set(InGameSet::Combat))
+         .add_systems(FixedUpdate, (
+        check_player_attack,
+        check_axol_attack,
+        check_spitter_attack,
         despawn_dead_entities
       ).in_set(InGameSet::Combat));
   }
 }
 
 #[derive(Default, Component)]
-pub struct Health(pub i8, pub u8);
+pub struct Health(pub i32, pub u32);
+
+// Whose side an entity is on. Projectiles only hurt the other side.
+#[derive(Default, Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Faction {
+  #[default]
+  Player,
+  Enemy,
+}
 
 
 #[derive(Default, Component, Deref, DerefMut)]
 pub struct AttackCooldown(pub Timer);
 
+// Multiplies an attacker's damage rolls.
+#[derive(Component, Clone, Copy, Deref)]
+pub struct DamageScale(pub f32);
+
+impl Default for DamageScale {
+  fn default() -> Self {
+    DamageScale(1.)
+  }
+}
+
+// Spits instead of biting: rears up for the windup, then fires the projectile
+// at where the player is by then.
+#[derive(Component, Clone, Deserialize)]
+pub struct RangedAttack {
+  // Seconds, long enough to see it coming.
+  pub windup: f32,
+  pub projectile: ProjectileStats,
+  // Fired at once, fanned out this many radians apart around the aim.
+  #[serde(default = "single_shot")]
+  pub shots: u8,
+  #[serde(default)]
+  pub spread: f32,
+  #[serde(skip)]
+  winding_up: Option<Timer>,
+}
+
+fn single_shot() -> u8 {
+  1
+}
+
+// An attack that lands on whatever the attacker's hitboxes touch while its
+// animation plays, hitting each target once.
+#[derive(Default, Component)]
+pub struct Strike {
+  active: bool,
+  hit: Vec<Entity>,
+}
+
+impl Strike {
+  pub fn start(&mut self) {
+    self.active = true;
+    self.hit.clear();
+  }
+}
+
+// What an animated entity is showing, and where.
+type Pose<'a> = (&'a Transform, &'a AnimState, &'a AnimFrame, &'a MoveDir, &'a Handle<AnimationSet>);
+
+// Targets the attacker's hitboxes touch on its current frame that the strike
+// hasn't hit yet. Targets are hit in their hurtboxes, or their collider on
+// frames without any. The strike ends once the attack has no hitboxes to come.
+fn land_strike<'a>(
+  strike: &mut Strike,
+  (transform, anim_state, frame, move_dir, animations): Pose,
+  targets: impl Iterator<Item = (Entity, Pose<'a>, Option<&'a Collider>)>,
+  animation_sets: &Assets<AnimationSet>,
+) -> Vec<Entity> {
+  let attacking = matches!(anim_state, AnimState::Attack | AnimState::AttackInjured);
+  let Some(animation_set) = animation_sets.get(animations).filter(|set| attacking && set.strikes_from(*anim_state, *move_dir, frame.0)) else {
+    strike.active = false;
+    return Vec::new();
+  };
+
+  let hitboxes = animation_set.hitboxes(*anim_state, *move_dir, frame.0, transform);
+  let mut hits = Vec::new();
+
+  for (target, (target_transform, target_state, target_frame, target_dir, target_animations), collider) in targets {
+    if strike.hit.contains(&target) {
+      continue;
+    }
+
+    let mut hurtboxes = animation_sets.get(target_animations)
+      .map_or_else(Vec::new, |set| set.hurtboxes(*target_state, *target_dir, target_frame.0, target_transform));
+    if hurtboxes.is_empty() {
+      hurtboxes.push(collider.copied().unwrap_or_default().bounds(target_transform.translation.xy()));
+    }
+
+    if hitboxes.iter().any(|hitbox| hurtboxes.iter().any(|hurtbox| !hitbox.intersect(*hurtbox).is_empty())) {
+      strike.hit.push(target);
+      hits.push(target);
+    }
+  }
+
+  hits
+}
 
 pub fn check_player_attack(
-  mut event: EventReader<SwordSwingEvent>,
-  axol: Query<(Entity, &Transform), With<Axol>>,
-  player: Query<(&Transform, &MoveDir), With<Player>>,
+  animation_sets: Res<Assets<AnimationSet>>,
+  mut player: Query<(&Transform, &AnimState, &AnimFrame, &MoveDir, &Handle<AnimationSet>, &mut Strike), With<Player>>,
+  axols: Query<(Entity, &Transform, &AnimState, &AnimFrame, &MoveDir, &Handle<AnimationSet>, &Health, Option<&Collider>), With<Axol>>,
   mut sword_miss: EventWriter<SwordMissEvent>,
   mut sword_hit: EventWriter<SwordHitEvent>,
+  mut rng: ResMut<GameRng>,
 ) {
-  let (player_transform, player_dir) = player.get_single().expect("Player despawned");
-
-  if event.read().next().is_some() {
-    for (entity, axol_transform) in axol.iter() {
-      let distance = axol_transform.translation.truncate().distance(player_transform.translation.truncate());
-
-      if distance < 70. {
-        // Make sure player is facing the enemy...
-        let player_facing = match player_dir {
-            MoveDir::Up => axol_transform.translation.y > player_transform.translation.y,
-            MoveDir::Left => axol_transform.translation.x < player_transform.translation.x,
-            MoveDir::Down => axol_transform.translation.y < player_transform.translation.y,
-            MoveDir::Right => axol_transform.translation.x > player_transform.translation.x,
-        };
-
-        if player_facing {
-          let mut rng = rand::thread_rng();
-          let damage = rng.gen_range(1..=6);
-
-          sword_hit.send(SwordHitEvent{ target: entity, amount: damage });
-        } else {
-          sword_miss.send_default();
-        }
-      } else {
-        //println!("{}", distance);
-        sword_miss.send_default();
-      }
-    }
+  let Ok((transform, anim_state, frame, move_dir, animations, mut strike)) = player.get_single_mut() else {
+    return;
+  };
+
+  if !strike.active {
+    return;
+  }
+
+  let targets = axols.iter()
+    .filter(|(_, _, _, _, _, _, health, _)| health.0 > 0)
+    .map(|(entity, transform, anim_state, frame, move_dir, animations, _, collider)| (entity, (transform, anim_state, frame, move_dir, animations), collider));
+
+  for target in land_strike(&mut strike, (transform, anim_state, frame, move_dir, animations), targets, &animation_sets) {
+    let damage = rng.gameplay.gen_range(1..=6);
+
+    sword_hit.send(SwordHitEvent{ target, amount: damage });
+  }
+
+  if !strike.active && strike.hit.is_empty() {
+    sword_miss.send_default();
   }
 }
 
 pub fn handle_player_damage (
   mut event: EventReader<AxolBiteEvent>,
-  mut player: Query<&mut Health, With<Player>>,
+  mut projectile_hits: EventReader<ProjectileHitEvent>,
+  mut player: Query<(Entity, &mut Health), With<Player>>,
   mut player_death: EventWriter<PlayerDeathEvent>
 ) {
-  for hit in event.read() {
-    let mut target_health = player.get_single_mut().expect("No Player");
+  let (player_entity, mut target_health) = player.get_single_mut().expect("No Player");
 
+  let projectile_damage = projectile_hits.read()
+    .filter(|hit| hit.target == player_entity)
+    .map(|hit| hit.amount);
+
+  for amount in event.read().map(|bite| bite.amount).chain(projectile_damage) {
     if target_health.0 > 0 {
-      target_health.0 -= hit.amount as i8;
+      target_health.0 -= amount as i32;
       if target_health.0 <= 0 {
         player_death.send_default();
       }
@@ -83,98 +177,131 @@ pub fn handle_player_damage (
 
 pub fn check_axol_attack(
   time: Res<Time>,
-  mut axol: Query<(&Transform, &mut AnimState, &MoveDir, &Health, &mut AttackCooldown), With<Axol>>,
-  player: Query<(&Transform, &Health), With<Player>>,
+  animation_sets: Res<Assets<AnimationSet>>,
+  mut axol: Query<(&Transform, &mut AnimState, &mut AnimFrame, &MoveDir, &Handle<AnimationSet>, &Facing, &Ai, &Health, &BiteDamage, &DamageScale, &mut AttackCooldown, &mut Strike), (With<Axol>, Without<RangedAttack>, Without<Player>)>,
+  player: Query<(Entity, &Transform, &AnimState, &AnimFrame, &MoveDir, &Handle<AnimationSet>, &Health, Option<&Collider>), With<Player>>,
   mut bite: EventWriter<AxolBiteEvent>,
+  mut rng: ResMut<GameRng>,
 ) {
-  let (player_transform, player_health) = player.get_single().expect("Player despawned");
-
-  for  (axol_transform, mut anim_state, axol_dir, axol_health, mut cooldown_timer) in axol.iter_mut() {
-    let distance = axol_transform.translation.truncate().distance(player_transform.translation.truncate());
+  let (player_entity, player_transform, player_state, player_frame, player_dir, player_animations, player_health, player_collider) = player.get_single().expect("Player despawned");
 
+  for (axol_transform, mut anim_state, mut anim_frame, move_dir, animations, axol_facing, ai, axol_health, bite_damage, damage_scale, mut cooldown_timer, mut strike) in axol.iter_mut() {
     cooldown_timer.tick(time.delta());
 
-    if distance < 70. && axol_health.0 > 0 && player_health.0 > 0 && cooldown_timer.just_finished() {
-      // Make sure axol is facing the player...
-      let axol_facing = match axol_dir {
-          MoveDir::Up => player_transform.translation.y > axol_transform.translation.y,
-          MoveDir::Left => player_transform.translation.x < axol_transform.translation.x,
-          MoveDir::Down => player_transform.translation.y < axol_transform.translation.y,
-          MoveDir::Right => player_transform.translation.x > axol_transform.translation.x,
-      };
+    if axol_health.0 <= 0 {
+      continue;
+    }
+
+    if strike.active {
+      // Lands during the bite frames, if the player is still in reach.
+      let target = (player_health.0 > 0).then_some((player_entity, (player_transform, player_state, player_frame, player_dir, player_animations), player_collider));
 
-      if axol_facing {
-        let mut rng = rand::thread_rng();
-        let damage = rng.gen_range(1..=6);
+      for _ in land_strike(&mut strike, (axol_transform, &*anim_state, &*anim_frame, move_dir, animations), target.into_iter(), &animation_sets) {
+        let roll: u8 = rng.gameplay.gen_range(bite_damage.min..=bite_damage.max.max(bite_damage.min));
+        let damage = (roll as f32 * **damage_scale).round().clamp(1., u8::MAX as f32) as u8;
 
         bite.send(AxolBiteEvent{ amount: damage });
+      }
+    } else if ai.state == AiState::Attack && player_health.0 > 0 && cooldown_timer.just_finished() {
+      // Make sure axol is facing the player...
+      let toward_player = (player_transform.translation - axol_transform.translation).truncate();
+
+      if axol_facing.dot(toward_player) > 0. {
+        strike.start();
+        anim_frame.0 = 0;
 
-        if axol_health.0 < axol_health.1 as i8{
+        if axol_health.0 < axol_health.1 as i32 {
           *anim_state = AnimState::AttackInjured;
         } else {
           *anim_state = AnimState::Attack;
         }
-      } else {
-        // switch directions  
       }
     }
   }
 }
 
-pub fn walk_to_player (
-    mut axol: Query<(Entity, &Transform, &mut AnimState, &mut MoveDir, &Health), With<Axol>>,
-    player: Query<(&Transform, &Health), With<Player>>,
-    mut walk_event: EventWriter<WalkEvent>,
+pub fn check_spitter_attack(
+  time: Res<Time>,
+  mut spitters: Query<(&Transform, &mut AnimState, &mut AnimFrame, &Ai, &Health, &DamageScale, &mut AttackCooldown, &mut RangedAttack), With<Axol>>,
+  player: Query<(&Transform, &Health), With<Player>>,
+  mut spawn_projectile: EventWriter<SpawnProjectileEvent>,
+  mut spit: EventWriter<SpitEvent>,
 ) {
-    let (player_transform, player_health) = player.get_single().expect("Player despawned");
-
-    for (
-        entity,
-        axol_transform,
-        mut anim_state,
-        mut axol_dir,
-        axol_health
-    ) in axol.iter_mut() {
-        // Calculate x and y distances
-        let dx = axol_transform.translation.x - player_transform.translation.x;
-        let dy = axol_transform.translation.y - player_transform.translation.y;
-
-        let distance = dx.hypot(dy);
-
-        if axol_health.0 > 0 && player_health.0 > 0 && distance > 70. {
-          if (dx.abs() > dy.abs()) && (dx > 0.) {
-              *axol_dir = MoveDir::Left;
-          } else if (dx.abs() > dy.abs()) && (dx < 0.) {
-              *axol_dir = MoveDir::Right;
-          } else if (dy.abs() > dx.abs()) && (dy > 0.) {
-              *axol_dir = MoveDir::Down;
-          } else if (dy.abs() > dx.abs()) && (dy < 0.) {
-              *axol_dir = MoveDir::Up;
-          }
-
-          walk_event.send(WalkEvent { direction: *axol_dir, entity });
-          *anim_state = AnimState::Walk;
+  let (player_transform, player_health) = player.get_single().expect("Player despawned");
+
+  for (transform, mut anim_state, mut anim_frame, ai, health, damage_scale, mut cooldown_timer, mut ranged) in spitters.iter_mut() {
+    cooldown_timer.tick(time.delta());
+
+    if health.0 <= 0 {
+      continue;
+    }
+
+    if let Some(windup) = ranged.winding_up.as_mut() {
+      if windup.tick(time.delta()).just_finished() {
+        let position = transform.translation.xy();
+        let aim = player_transform.translation.xy() - position;
+        let damage = (ranged.projectile.damage as f32 * **damage_scale).round().clamp(1., u8::MAX as f32) as u8;
+
+        for shot in 0..ranged.shots {
+          let angle = (shot as f32 - (ranged.shots - 1) as f32 / 2.) * ranged.spread;
+
+          spawn_projectile.send(SpawnProjectileEvent {
+            owner: Faction::Enemy,
+            position,
+            direction: Vec2::from_angle(angle).rotate(aim),
+            stats: ProjectileStats { damage, ..ranged.projectile },
+          });
         }
+        spit.send_default();
+        ranged.winding_up = None;
+      }
+    } else if ai.state == AiState::Attack && player_health.0 > 0 && cooldown_timer.just_finished() {
+      ranged.winding_up = Some(Timer::from_seconds(ranged.windup, TimerMode::Once));
+      anim_frame.0 = 0;
+
+      if health.0 < health.1 as i32 {
+        *anim_state = AnimState::AttackInjured;
+      } else {
+        *anim_state = AnimState::Attack;
+      }
     }
+  }
 }
 
-
 pub fn handle_axol_damage (
+  mut commands: Commands,
   mut score: ResMut<Score>,
   mut event: EventReader<SwordHitEvent>,
-  mut axol_list: Query<(&mut AnimState, &mut AnimFrame, &mut Health, &mut AttackCooldown), With<Axol>>,
-  mut axol_death: EventWriter<AxolDeath>
+  mut projectile_hits: EventReader<ProjectileHitEvent>,
+  mut axol_list: Query<(&mut AnimState, &mut AnimFrame, &mut Health, &mut AttackCooldown, &ScoreValue, Option<&RangedAttack>, Option<&Boss>), With<Axol>>,
+  mut axol_death: EventWriter<AxolDeath>,
+  mut spitter_death: EventWriter<SpitterDeath>
 ) {
-  for hit in event.read() {
-    let (mut target_state, mut target_frame, mut target_health, mut cooldown_timer) = axol_list.get_mut(hit.target).expect("No target for attack");
+  let sword_hits = event.read().map(|hit| (hit.target, hit.amount));
+  let projectile_damage = projectile_hits.read().map(|hit| (hit.target, hit.amount));
+
+  for (target, amount) in sword_hits.chain(projectile_damage) {
+    // Projectiles hit the player too, and the target may be gone already.
+    let Ok((mut target_state, mut target_frame, mut target_health, mut cooldown_timer, score_value, ranged, boss)) = axol_list.get_mut(target) else {
+      continue;
+    };
 
     if target_health.0 > 0 {
-      target_health.0 -= hit.amount as i8;
+      target_health.0 -= amount as i32;
       if target_health.0 <= 0 {
         *target_state = AnimState::Dead;
         *target_frame = sprite::AnimFrame(0);
-        axol_death.send_default();
-        **score += 100;
+        // Others can walk over the body, and a spitter killed mid windup
+        // never gets its spit out.
+        commands.entity(target).remove::<(Separation, RangedAttack)>();
+
+        // A boss spitting in its last phase is still no spitter.
+        if ranged.is_some() && boss.is_none() {
+          spitter_death.send_default();
+        } else {
+          axol_death.send_default();
+        }
+        **score += **score_value;
         *cooldown_timer = AttackCooldown(Timer::from_seconds(8.0, TimerMode::Once));
       }
     }
@@ -0,0 +1,117 @@
+use bevy::{asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext}, prelude::*, utils::BoxedFuture};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{axol::BiteDamage, behaviour::Behaviour, boss::BossDefinition, collision::Collider, combat::RangedAttack, game::RequiredAssets, movement::Separation, sprite::{AnimationSet, AtlasDefinition}};
+
+pub struct EnemiesPlugin;
+
+impl Plugin for EnemiesPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_asset::<EnemyTable>()
+         .init_asset_loader::<EnemyTableLoader>()
+         .init_resource::<EnemyLibrary>();
+  }
+}
+
+// Every kind of enemy, by the name waves spawn it by, loaded from
+// assets/enemies.enemies.ron.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct EnemyTable {
+  enemies: HashMap<String, EnemyType>,
+}
+
+impl EnemyTable {
+  pub fn get(&self, kind: &str) -> Option<&EnemyType> {
+    self.enemies.get(kind)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &EnemyType)> {
+    self.enemies.iter()
+  }
+}
+
+#[derive(Deserialize)]
+pub struct EnemyType {
+  // Sprite sheets, by the name the animations use for them. Kinds can share
+  // another kind's.
+  #[serde(default)]
+  pub atlases: HashMap<String, AtlasDefinition>,
+  // Path of the animation set, e.g. "animations/axol.anim.ron".
+  pub animations: String,
+  // Loaded along with the table.
+  #[serde(skip)]
+  pub animation_set: Handle<AnimationSet>,
+  pub health: u32,
+  // Pixels per second.
+  pub speed: f32,
+  // Rolled for every bite, before the wave's damage multiplier.
+  pub damage: BiteDamage,
+  // Seconds between attacks.
+  pub cooldown: f32,
+  // Added to the score for a kill.
+  pub score: usize,
+  pub collider: Collider,
+  pub separation: Separation,
+  #[serde(default)]
+  pub behaviour: Behaviour,
+  // Spits from range instead of biting.
+  #[serde(default)]
+  pub ranged: Option<RangedAttack>,
+  // Colours the sprite, to tell kinds sharing a sprite sheet apart.
+  #[serde(default)]
+  pub tint: Option<Color>,
+  #[serde(default)]
+  pub boss: Option<BossDefinition>,
+}
+
+// Loads the table like any RON asset, then the animation set of every enemy
+// in it so they are ready as soon as the table is.
+#[derive(Default)]
+struct EnemyTableLoader;
+
+impl AssetLoader for EnemyTableLoader {
+  type Asset = EnemyTable;
+  type Settings = ();
+  type Error = Box<dyn std::error::Error + Send + Sync>;
+
+  fn load<'a>(
+    &'a self,
+    reader: &'a mut Reader,
+    _settings: &'a (),
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<EnemyTable, Self::Error>> {
+    Box::pin(async move {
+      let mut bytes = Vec::new();
+      reader.read_to_end(&mut bytes).await?;
+
+      let mut table: EnemyTable = ron::de::from_bytes(&bytes)?;
+      for enemy in table.enemies.values_mut() {
+        enemy.animation_set = load_context.load(enemy.animations.clone());
+      }
+
+      Ok(table)
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["enemies.ron"]
+  }
+}
+
+#[derive(Resource)]
+pub struct EnemyLibrary {
+  pub enemies: Handle<EnemyTable>,
+}
+
+impl FromWorld for EnemyLibrary {
+  fn from_world(world: &mut World) -> Self {
+    let library = EnemyLibrary {
+      enemies: world.resource::<AssetServer>().load("enemies.enemies.ron"),
+    };
+
+    world.get_resource_or_insert_with(RequiredAssets::default).add(&library.enemies);
+
+    library
+  }
+}
@@ -1,14 +1,12 @@
 use bevy::prelude::*;
 
-use crate::sprite::MoveDir;
+use crate::{combat::Faction, projectile::ProjectileStats};
 
 pub struct EventPlugin;
 
 impl Plugin for EventPlugin {
   fn build(&self, app: &mut App) {
       app.add_event::<StartGameEvent>()
-         .add_event::<WalkEvent>()
-         .add_event::<RunEvent>()
          .add_event::<SwordSwingEvent>()
          .add_event::<SwordMissEvent>()
          .add_event::<SwordHitEvent>()
@@ -16,26 +14,20 @@ impl Plugin for EventPlugin {
          .add_event::<AxolDeath>()
          .add_event::<PlayerDeathEvent>()
          .add_event::<GameOverEvent>()
-         .add_event::<NewWaveEvent>();
+         .add_event::<NewWaveEvent>()
+         .add_event::<VictoryEvent>()
+         .add_event::<SpawnProjectileEvent>()
+         .add_event::<ProjectileHitEvent>()
+         .add_event::<SpitEvent>()
+         .add_event::<SpitterDeath>()
+         .add_event::<BossPhaseEvent>()
+         .add_event::<AnimationFrameEvent>();
   }
 }
 
 #[derive(Event, Default)]
 pub struct StartGameEvent;
 
-
-#[derive(Event)]
-pub struct WalkEvent {
-  pub direction: MoveDir,
-  pub entity:Entity,
-}
-
-#[derive(Event, Default)]
-pub struct RunEvent {
-  pub direction: MoveDir
-}
-
-
 #[derive(Event, Default)]
 pub struct SwordSwingEvent;
 
@@ -65,3 +57,36 @@ pub struct GameOverEvent;
 
 #[derive(Event, Default)]
 pub struct NewWaveEvent;
+
+#[derive(Event, Default)]
+pub struct VictoryEvent;
+
+#[derive(Event)]
+pub struct SpawnProjectileEvent {
+  pub owner: Faction,
+  pub position: Vec2,
+  pub direction: Vec2,
+  pub stats: ProjectileStats,
+}
+
+#[derive(Event, Debug)]
+pub struct ProjectileHitEvent {
+  pub target: Entity,
+  pub amount: u8
+}
+
+#[derive(Event, Default)]
+pub struct SpitEvent;
+
+#[derive(Event, Default)]
+pub struct SpitterDeath;
+
+#[derive(Event, Default)]
+pub struct BossPhaseEvent;
+
+// The entity's animation reached a frame that names this event.
+#[derive(Event, Debug)]
+pub struct AnimationFrameEvent {
+  pub entity: Entity,
+  pub name: String
+}
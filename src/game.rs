@@ -1,35 +1,104 @@
-use bevy::prelude::*;
+use bevy::{asset::LoadState, prelude::*};
 
-use crate::{axol::Axol, combat::Health, events::{GameOverEvent, NewWaveEvent, PlayerDeathEvent, StartGameEvent}, player::Player, score::Score, spawner::{SpawnTimer, Spawner, SpawnerBundle, WaveCount}, sprite::{AnimFrame, AnimState}};
+use crate::{actions::{Action, Actions}, axol::Axol, campaign::{Campaign, CampaignLibrary}, combat::Health, events::{GameOverEvent, NewWaveEvent, PlayerDeathEvent, StartGameEvent, VictoryEvent}, highscores::HighScores, player::Player, rng::GameRng, score::Score, spawner::{SpawnTimer, SpawnWave, Spawner, SpawnerBundle, WaveCount}, sprite::{AnimFrame, AnimState}, waves::{WaveLibrary, WaveTable}};
 
 
+// Every Update system that advances the game. Replays hold this back to pause
+// or step through a recording. FixedUpdate only advances with time, so it is
+// held back through the time step instead.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct SimulationSet;
+
+// Movement, combat timers and spawning run on FixedUpdate at this rate so the
+// game plays the same whatever the display refresh rate.
+const SIMULATION_HZ: f64 = 60.;
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 pub enum InGameSet {
   PlayAudio,
   PlayerMovement,
+  Movement,
   Camera,
   Combat,
   Restart,
+  Transition,
   Score
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
+    Loading,
     Splash,
     InGame,
     Paused,
     NextWave,
     Restart,
+    LevelTransition,
+    Victory,
+    NameEntry,
+    Rebinding,
 }
 
 #[derive(Resource, Default)]
 pub struct Game {
   wave_number: u8,
+  // Waves started this run, the one in progress included.
+  wave_reached: u8,
+  // Index into the campaign's levels.
+  level: usize,
   lives_remaining: u8,
   high_score: usize
 }
 
+impl Game {
+  pub fn wave_number(&self) -> u8 {
+    self.wave_number
+  }
+
+  // Counts from 1, and stays on the last wave cleared until the next starts.
+  pub fn wave_reached(&self) -> u8 {
+    self.wave_reached
+  }
+
+  pub fn start_wave(&mut self) {
+    self.wave_reached = self.wave_number.saturating_add(1);
+  }
+
+  // Moves on past the wave in progress and returns it.
+  pub fn clear_wave(&mut self) -> u8 {
+    let cleared = self.wave_number;
+    self.wave_number = cleared.saturating_add(1);
+    cleared
+  }
+
+  pub fn level(&self) -> usize {
+    self.level
+  }
+
+  pub fn lives_remaining(&self) -> u8 {
+    self.lives_remaining
+  }
+
+  pub fn high_score(&self) -> usize {
+    self.high_score
+  }
+
+  // Keeps the best score seen, whichever run it came from.
+  pub fn raise_high_score(&mut self, score: usize) {
+    self.high_score = self.high_score.max(score);
+  }
+
+  // Picks up a saved run where it left off.
+  pub fn resume(&mut self, wave_number: u8, level: usize, lives_remaining: u8, high_score: usize) {
+    self.wave_number = wave_number;
+    self.wave_reached = wave_number;
+    self.level = level;
+    self.lives_remaining = lives_remaining;
+    self.raise_high_score(high_score);
+  }
+}
+
 
 #[derive(Default, Resource, Deref, DerefMut)]
 struct GameTimer(Timer);
@@ -39,14 +108,19 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
   fn build(&self, app: &mut App) {
     app.init_resource::<Game>()
+       .init_resource::<RequiredAssets>()
        .init_resource::<GameTimer>()
+       .insert_resource(Score(0))
+       .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
        .add_state::<GameState>()
-       .add_systems(OnEnter(GameState::Splash), splash_setup)
-       .add_systems(OnExit(GameState::Splash), (despawn_screen::<OnSplashScreen>, setup_new_game))
-       .add_systems(Update, (handle_game_over, setup_spawner))
-       .add_systems(Update, (wait_for_restart).in_set(InGameSet::Restart))
+       .add_systems(OnEnter(GameState::Loading), pause_time)
+       .add_systems(OnExit(GameState::Loading), unpause_time)
+       .add_systems(Update, (wait_for_assets).run_if(in_state(GameState::Loading)))
+       .add_systems(OnTransition { from: GameState::Splash, to: GameState::InGame }, setup_new_game)
+       .add_systems(Update, (handle_game_over, handle_victory, setup_spawner).in_set(SimulationSet))
+       .add_systems(FixedUpdate, (wait_for_restart).in_set(InGameSet::Restart))
        .add_systems(Update, (check_for_wave_cleared).in_set(InGameSet::Score))
-       .add_systems(Update, (game_state_input_events, handle_player_death))
+       .add_systems(Update, (game_state_input_events, handle_player_death).in_set(SimulationSet))
        .configure_sets(
       Update,
        (
@@ -62,36 +136,128 @@ impl Plugin for GamePlugin {
        (
         InGameSet::Restart,
        ).run_if(in_state(GameState::Restart))
+    )
+    .configure_sets(
+      Update,
+       (
+        InGameSet::Camera,
+        InGameSet::Combat,
+        InGameSet::PlayAudio,
+        InGameSet::PlayerMovement,
+        InGameSet::Restart,
+        InGameSet::Score
+       ).in_set(SimulationSet)
+    )
+    .configure_sets(
+      FixedUpdate,
+       (
+        InGameSet::Combat,
+        InGameSet::Movement,
+       ).chain().run_if(in_state(GameState::InGame))
+    )
+    .configure_sets(
+      FixedUpdate,
+       (
+        InGameSet::Restart,
+       ).run_if(in_state(GameState::Restart))
+    )
+    .configure_sets(
+      FixedUpdate,
+       (
+        InGameSet::Transition,
+       ).run_if(in_state(GameState::LevelTransition))
     );
   }
 }
 
-fn setup_new_game (
+// Assets the game can't start without. It stays in Loading until they are all
+// in.
+#[derive(Default, Resource)]
+pub struct RequiredAssets(Vec<UntypedHandle>);
+
+impl RequiredAssets {
+  pub fn add<A: Asset>(&mut self, handle: &Handle<A>) {
+    self.0.push(handle.clone().untyped());
+  }
+}
+
+fn wait_for_assets(
+  asset_server: Res<AssetServer>,
+  required: Res<RequiredAssets>,
+  mut next_state: ResMut<NextState<GameState>>,
+) {
+  for handle in required.0.iter() {
+    if asset_server.get_load_state(handle.id()) == Some(LoadState::Failed) {
+      panic!("Unable to load {:?}", handle.path());
+    }
+  }
+
+  if required.0.iter().all(|handle| asset_server.is_loaded_with_dependencies(handle.id())) {
+    next_state.set(GameState::Splash);
+  }
+}
+
+// Nothing is simulated while assets load, however long that takes.
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+  time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+  time.unpause();
+}
+
+pub fn setup_new_game (
   mut game: ResMut<Game>,
   mut score: ResMut<Score>,
+  mut rng: ResMut<GameRng>,
   mut startgame_event: EventWriter<StartGameEvent>,
   mut newwave_event: EventWriter<NewWaveEvent>,
 ) {
   game.wave_number = 0;
+  game.wave_reached = 0;
+  game.level = 0;
   game.lives_remaining = 3;
 
   *score = Score(0);
+  rng.start_run();
 
   startgame_event.send_default();
   newwave_event.send_default();
 }
 
+// Runs good enough for the high-score table get their initials entered first.
+fn state_after_run(high_scores: &HighScores, score: &Score) -> GameState {
+  if high_scores.qualifies(**score) {
+    GameState::NameEntry
+  } else {
+    GameState::Splash
+  }
+}
+
 fn game_state_input_events(
     mut next_state: ResMut<NextState<GameState>>,
     state: Res<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Actions,
+    mut new_wave: EventWriter<NewWaveEvent>,
 ) {
 
-  if keyboard_input.just_pressed(KeyCode::S) && *state.get() == GameState::Splash {
-    next_state.set(GameState::InGame);
+  if actions.just_pressed(Action::Start) {
+    match state.get() {
+        GameState::Splash => next_state.set(GameState::InGame),
+        // Winning the campaign doesn't end the run, the waves keep coming.
+        GameState::Victory => {
+          new_wave.send_default();
+          next_state.set(GameState::NextWave);
+        },
+        _ => (),
+    }
+  }
+
+  if actions.just_pressed(Action::Rebind) && *state.get() == GameState::Splash {
+    next_state.set(GameState::Rebinding);
   }
 
-  if keyboard_input.just_pressed(KeyCode::Escape) {
+  if actions.just_pressed(Action::Pause) {
     match state.get() {
         GameState::InGame => next_state.set(GameState::Paused),
         GameState::Paused => next_state.set(GameState::InGame),
@@ -100,51 +266,6 @@ fn game_state_input_events(
   }
 }
 
-// Tag component used to tag entities added on the splash screen
-#[derive(Component)]
-struct OnSplashScreen;
-
-
-fn splash_setup(
-  mut commands: Commands,
-  asset_server: Res<AssetServer>
-) {
-  let icon = asset_server.load("screens/splash.png");
-  // Display the logo
-  commands
-      .spawn((
-          NodeBundle {
-              style: Style {
-                  align_items: AlignItems::Center,
-                  justify_content: JustifyContent::Center,
-                  width: Val::Percent(100.0),
-                  height: Val::Percent(100.0),
-                  ..default()
-              },
-              ..default()
-          },
-          OnSplashScreen,
-      ))
-      .with_children(|parent| {
-          parent.spawn(ImageBundle {
-              style: Style {
-                  // This will set the logo to be 200px wide, and auto adjust its height
-                  width: Val::Px(1280.0),
-                  ..default()
-              },
-              image: UiImage::new(icon),
-              ..default()
-          });
-      });
-}
-
-// Generic system that takes a component as a parameter, and will despawn all entities with that component
-fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
-    for entity in &to_despawn {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 fn handle_player_death(
   mut commands: Commands,
   mut event: EventReader<PlayerDeathEvent>,
@@ -183,7 +304,7 @@ fn wait_for_restart(
 
     *state = AnimState::Idle;
     *frame = AnimFrame(0);
-    health.0 = health.1 as i8;
+    health.0 = health.1 as i32;
     next_state.set(GameState::InGame);
   }
 }
@@ -195,40 +316,57 @@ fn handle_game_over(
   mut wave_events: EventReader<NewWaveEvent>,
   mut next_state: ResMut<NextState<GameState>>,
   mut game: ResMut<Game>,
-  score: Res<Score>
+  score: Res<Score>,
+  high_scores: Res<HighScores>,
 ) {
   if event.read().next().is_some() {
     for entity in entities.iter() {
       commands.entity(entity).despawn_recursive();
     }
     wave_events.clear();
-    next_state.set(GameState::Splash);
+    next_state.set(state_after_run(&high_scores, &score));
 
-    if **score > game.high_score {
-      game.high_score = **score;
+    game.raise_high_score(**score);
+  }
+}
+
+// The campaign is won once its last level is cleared.
+fn handle_victory(
+  mut commands: Commands,
+  mut event: EventReader<VictoryEvent>,
+  axols: Query<Entity, With<Axol>>,
+  mut game: ResMut<Game>,
+  score: Res<Score>
+) {
+  if event.read().next().is_some() {
+    for entity in axols.iter() {
+      commands.entity(entity).despawn_recursive();
     }
+
+    game.raise_high_score(**score);
   }
 }
 
 pub fn setup_spawner(
   mut commands: Commands,
-  game: Res<Game>,
+  mut game: ResMut<Game>,
+  wave_library: Res<WaveLibrary>,
+  wave_tables: Res<Assets<WaveTable>>,
   mut event: EventReader<NewWaveEvent>,
   mut next_state: ResMut<NextState<GameState>>,
 ) {
   if event.read().next().is_some() {
-    let (wave_count, duration) = match game.wave_number {
-      1..=2 => (2, 8.0),
-      3..=4 => (4, 6.0),
-      5..=10 => (6, 5.0),
-      _ => (10, 4.0)
-    };
-    println!("{} {} {}", game.wave_number, wave_count, duration);
+    let wave = wave_tables.get(&wave_library.waves)
+      .and_then(|table| table.wave(game.wave_number as usize))
+      .expect("Wave table is empty");
+
     commands.spawn(SpawnerBundle {
       spawner: Spawner,
-      rate: SpawnTimer(Timer::from_seconds(duration, TimerMode::Repeating)),
-      remaining: WaveCount(wave_count)
+      rate: SpawnTimer(Timer::from_seconds(wave.interval, TimerMode::Repeating)),
+      remaining: WaveCount(wave.total()),
+      wave: SpawnWave(wave)
     });
+    game.start_wave();
     next_state.set(GameState::InGame);
     event.clear();
   }
@@ -237,11 +375,22 @@ pub fn setup_spawner(
 pub fn check_for_wave_cleared(
   mut commands: Commands,
   mut game: ResMut<Game>,
+  campaigns: Res<Assets<Campaign>>,
+  campaign_library: Res<CampaignLibrary>,
   spawner: Query<(Entity, &WaveCount), With<Spawner>>,
   enemies: Query<&Health, With<Axol>>,
   mut event: EventWriter<NewWaveEvent>,
+  mut victory: EventWriter<VictoryEvent>,
   mut next_state: ResMut<NextState<GameState>>,
 ) {
+  // A wave can't be cleared before its spawner is up, which is a frame after
+  // the wave starts.
+  if spawner.is_empty() {
+    return;
+  }
+
+  // The boss spawns last and is an axol like any other, so a boss wave lasts
+  // until it dies.
   let mut total = 0;
 
   for (_entity, count) in spawner.iter() {
@@ -255,12 +404,25 @@ pub fn check_for_wave_cleared(
   }
 
   if total == 0 {
-    game.wave_number += 1;
+    let cleared = game.clear_wave();
 
     for (entity, _) in spawner.iter() {
       commands.entity(entity).despawn_recursive();
     }
-    event.send_default();
-    next_state.set(GameState::NextWave);
+
+    let campaign = campaigns.get(&campaign_library.campaign).expect("Campaign not loaded");
+
+    // Past the campaign's last wave the run stays on its last level, with
+    // waves growing past the end of the wave table.
+    if campaign.is_last_wave(cleared) {
+      victory.send_default();
+      next_state.set(GameState::Victory);
+    } else if let Some(level) = campaign.level_for_wave(game.wave_number).filter(|level| *level != game.level) {
+      game.level = level;
+      next_state.set(GameState::LevelTransition);
+    } else {
+      event.send_default();
+      next_state.set(GameState::NextWave);
+    }
   }
 }
\ No newline at end of file
@@ -0,0 +1,253 @@
+use bevy::{app::AppExit, core::FrameCount, input::{gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent, GamepadInfo}, InputSystem}, prelude::*, time::TimeUpdateStrategy};
+use std::time::Duration;
+
+use crate::{actions::{Action, Binding, InputBindings}, axol::Axol, combat::Health, events::{GameOverEvent, VictoryEvent}, game::{Game, GameState}, movement::Facing, options::LaunchOptions, player::Player, rng::GameRng, score::Score};
+
+// Every frame advances the simulation by the same amount, however fast the
+// machine runs it.
+const SIMULATION_STEP: f32 = 1. / 60.;
+const AUTOPILOT_ATTACK_RANGE: f32 = 60.;
+// Seconds of play between unplugging and replugging the autopilot's gamepad.
+const AUTOPILOT_REPLUG_TIME: f32 = 30.;
+
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(SIMULATION_STEP)))
+       .add_systems(Last, (report_game_over, report_victory, stop_after_max_frames));
+
+    let options = app.world.resource::<LaunchOptions>().clone();
+
+    // A replay supplies its own input.
+    if options.replay.is_some() {
+      return;
+    }
+
+    app.init_resource::<Autopilot>();
+
+    // With --gamepad the autopilot plays on a synthetic gamepad instead, sent
+    // as the same events a real one produces.
+    if options.gamepad {
+      app.init_resource::<AutopilotPad>()
+         .add_systems(PreUpdate, autopilot_gamepad.before(InputSystem));
+    } else {
+      app.add_systems(PreUpdate, autopilot_keyboard.after(InputSystem));
+    }
+  }
+}
+
+// Plays the game through the regular keyboard or gamepad input so the same
+// systems run as for a real player.
+#[derive(Resource)]
+struct Autopilot {
+  swing: Timer
+}
+
+impl Default for Autopilot {
+  fn default() -> Self {
+    Autopilot {
+      swing: Timer::from_seconds(0.4, TimerMode::Repeating)
+    }
+  }
+}
+
+// What the autopilot wants to do this frame, if anything.
+fn choose_action(
+  time: &Time,
+  state: &GameState,
+  autopilot: &mut Autopilot,
+  player: &Query<(&Transform, &Facing), With<Player>>,
+  axols: &Query<(&Transform, &Health), With<Axol>>,
+) -> Option<Action> {
+  match state {
+    GameState::Splash | GameState::Victory => Some(Action::Start),
+    GameState::Paused => Some(Action::Pause),
+    GameState::InGame => {
+      let (player_transform, player_facing) = player.get_single().ok()?;
+      let player_position = player_transform.translation.truncate();
+
+      let offset = axols.iter()
+        .filter(|(_, health)| health.0 > 0)
+        .map(|(transform, _)| transform.translation.truncate() - player_position)
+        .min_by(|a, b| a.length().total_cmp(&b.length()))?;
+
+      let action = if offset.x.abs() > offset.y.abs() {
+        if offset.x > 0. { Action::MoveRight } else { Action::MoveLeft }
+      } else if offset.y > 0. {
+        Action::MoveUp
+      } else {
+        Action::MoveDown
+      };
+
+      if offset.length() > AUTOPILOT_ATTACK_RANGE || player_facing.dot(offset) <= 0. {
+        Some(action)
+      } else if autopilot.swing.tick(time.delta()).just_finished() {
+        Some(Action::Attack)
+      } else {
+        None
+      }
+    },
+    _ => None,
+  }
+}
+
+fn autopilot_keyboard(
+  time: Res<Time>,
+  state: Res<State<GameState>>,
+  mut autopilot: ResMut<Autopilot>,
+  mut keys: ResMut<Input<KeyCode>>,
+  bindings: Res<InputBindings>,
+  player: Query<(&Transform, &Facing), With<Player>>,
+  axols: Query<(&Transform, &Health), With<Axol>>,
+) {
+  // Let go of last frame's keys so every press registers as a new one.
+  keys.release_all();
+
+  if let Some(key) = choose_action(&time, state.get(), &mut autopilot, &player, &axols).and_then(|action| bindings.key(action)) {
+    keys.press(key);
+  }
+}
+
+// The autopilot's gamepad, which it unplugs and plugs back in now and then to
+// check the game copes.
+#[derive(Resource)]
+struct AutopilotPad {
+  connected: bool,
+  replug: Timer,
+  held: Vec<GamepadButtonType>,
+  stick: Vec2,
+}
+
+impl Default for AutopilotPad {
+  fn default() -> Self {
+    AutopilotPad {
+      connected: false,
+      replug: Timer::from_seconds(AUTOPILOT_REPLUG_TIME, TimerMode::Repeating),
+      held: Vec::new(),
+      stick: Vec2::ZERO,
+    }
+  }
+}
+
+fn autopilot_gamepad(
+  time: Res<Time>,
+  state: Res<State<GameState>>,
+  mut autopilot: ResMut<Autopilot>,
+  mut pad: ResMut<AutopilotPad>,
+  bindings: Res<InputBindings>,
+  player: Query<(&Transform, &Facing), With<Player>>,
+  axols: Query<(&Transform, &Health), With<Axol>>,
+  mut events: EventWriter<GamepadEvent>,
+) {
+  let gamepad = Gamepad::new(0);
+
+  if !pad.connected {
+    let info = GamepadInfo { name: "Autopilot".to_string() };
+    events.send(GamepadEvent::Connection(GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected(info))));
+    pad.connected = true;
+    return;
+  }
+
+  if *state.get() == GameState::InGame && pad.replug.tick(time.delta()).just_finished() {
+    events.send(GamepadEvent::Connection(GamepadConnectionEvent::new(gamepad, GamepadConnection::Disconnected)));
+    *pad = AutopilotPad { replug: pad.replug.clone(), ..default() };
+    return;
+  }
+
+  let mut held = Vec::new();
+  let mut stick = Vec2::ZERO;
+
+  match choose_action(&time, state.get(), &mut autopilot, &player, &axols).and_then(|action| bindings.pad(action)) {
+    Some(Binding::Pad(button)) => held.push(button),
+    Some(Binding::Stick(GamepadAxisType::LeftStickX, direction)) => stick.x = direction.sign(),
+    Some(Binding::Stick(GamepadAxisType::LeftStickY, direction)) => stick.y = direction.sign(),
+    _ => (),
+  }
+
+  for button in pad.held.iter().filter(|button| !held.contains(button)) {
+    events.send(GamepadEvent::Button(GamepadButtonChangedEvent::new(gamepad, *button, 0.)));
+  }
+  for button in held.iter().filter(|button| !pad.held.contains(button)) {
+    events.send(GamepadEvent::Button(GamepadButtonChangedEvent::new(gamepad, *button, 1.)));
+  }
+  if stick.x != pad.stick.x {
+    events.send(GamepadEvent::Axis(GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, stick.x)));
+  }
+  if stick.y != pad.stick.y {
+    events.send(GamepadEvent::Axis(GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickY, stick.y)));
+  }
+
+  pad.held = held;
+  pad.stick = stick;
+}
+
+fn print_report(reason: &str, game: &Game, score: &Score, rng: &GameRng, frame_count: &FrameCount) {
+  println!(
+    "{} after {} frames with seed {}: level {}, wave {}, score {}, lives remaining {}, high score {}",
+    reason,
+    frame_count.0,
+    rng.seed(),
+    game.level() + 1,
+    game.wave_number(),
+    **score,
+    game.lives_remaining(),
+    game.high_score()
+  );
+}
+
+// A run the autopilot ends without clearing a single wave means something is
+// broken, e.g. assets that never load or enemies that never spawn, so the run
+// fails rather than passing on its exit code alone. Replays play out whatever
+// was recorded.
+fn check_autopilot_progress(game: &Game, autopilot: bool) {
+  if autopilot && game.wave_number() == 0 {
+    eprintln!("The autopilot didn't clear a single wave");
+    std::process::exit(1);
+  }
+}
+
+fn report_game_over(
+  mut event: EventReader<GameOverEvent>,
+  mut exit: EventWriter<AppExit>,
+  autopilot: Option<Res<Autopilot>>,
+  game: Res<Game>,
+  score: Res<Score>,
+  rng: Res<GameRng>,
+  frame_count: Res<FrameCount>,
+) {
+  if event.read().next().is_some() {
+    print_report("Game over", &game, &score, &rng, &frame_count);
+    check_autopilot_progress(&game, autopilot.is_some());
+    exit.send(AppExit);
+  }
+}
+
+// The autopilot plays on past the campaign until the game is over.
+fn report_victory(
+  mut event: EventReader<VictoryEvent>,
+  game: Res<Game>,
+  score: Res<Score>,
+  rng: Res<GameRng>,
+  frame_count: Res<FrameCount>,
+) {
+  if event.read().next().is_some() {
+    print_report("Victory", &game, &score, &rng, &frame_count);
+  }
+}
+
+fn stop_after_max_frames(
+  options: Res<LaunchOptions>,
+  mut exit: EventWriter<AppExit>,
+  autopilot: Option<Res<Autopilot>>,
+  game: Res<Game>,
+  score: Res<Score>,
+  rng: Res<GameRng>,
+  frame_count: Res<FrameCount>,
+) {
+  if options.max_frames.is_some_and(|max_frames| frame_count.0 >= max_frames) {
+    print_report("Stopped", &game, &score, &rng, &frame_count);
+    check_autopilot_progress(&game, autopilot.is_some());
+    exit.send(AppExit);
+  }
+}
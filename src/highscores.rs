@@ -0,0 +1,402 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{actions::{Action, Actions}, game::{Game, GameState, SimulationSet}, options::{user_data_path, LaunchOptions}, rng::GameRng, score::Score, splash::despawn_screen};
+
+// Entries kept in the table.
+const HIGH_SCORE_COUNT: usize = 10;
+const MAX_INITIALS: usize = 3;
+
+const TABLE_FONT_SIZE: f32 = 40.0;
+const TABLE_TEXT_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+const TABLE_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_resource::<HighScores>()
+         .init_resource::<Initials>()
+         .add_systems(Startup, seed_high_score)
+         .add_systems(OnEnter(GameState::NameEntry), clear_initials)
+         .add_systems(Update, (enter_initials).run_if(in_state(GameState::NameEntry)).in_set(SimulationSet));
+  }
+}
+
+// Shows the name entry screen, and the table over the splash screen.
+pub struct HighScoresScreenPlugin;
+
+impl Plugin for HighScoresScreenPlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(OnEnter(GameState::NameEntry), name_entry_setup)
+         .add_systems(Update, (update_name_entry).run_if(in_state(GameState::NameEntry)))
+         .add_systems(OnExit(GameState::NameEntry), despawn_screen::<OnNameEntryScreen>)
+         .add_systems(Update, (toggle_high_score_table).run_if(in_state(GameState::Splash)))
+         .add_systems(OnExit(GameState::Splash), despawn_screen::<OnHighScoreTable>);
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+  pub initials: String,
+  pub score: usize,
+  pub wave: u8,
+  // YYYY-MM-DD, in UTC.
+  pub date: String,
+  pub seed: u64,
+}
+
+// The best runs on this machine, best first. Only regular sessions read and
+// write the file, other runs get an empty table that is thrown away.
+#[derive(Resource)]
+pub struct HighScores {
+  entries: Vec<HighScoreEntry>,
+  path: Option<PathBuf>,
+}
+
+impl FromWorld for HighScores {
+  fn from_world(world: &mut World) -> Self {
+    let path = world.get_resource::<LaunchOptions>()
+      .filter(|options| options.uses_user_data())
+      .and_then(|_| user_data_path("highscores.ron"));
+
+    let entries = match &path {
+      Some(path) if path.is_file() => HighScores::load(path).unwrap_or_else(|e| {
+        warn!("Unable to read high scores from {}: {}", path.display(), e);
+        Vec::new()
+      }),
+      _ => Vec::new(),
+    };
+
+    HighScores { entries, path }
+  }
+}
+
+impl HighScores {
+  fn load(path: &Path) -> Result<Vec<HighScoreEntry>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+  }
+
+  fn save(&self) {
+    let Some(path) = &self.path else {
+      return;
+    };
+
+    let result = path.parent()
+      .map_or(Ok(()), std::fs::create_dir_all)
+      .map_err(|e| e.to_string())
+      .and_then(|_| ron::ser::to_string_pretty(&self.entries, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string()))
+      .and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+      error!("Unable to save high scores to {}: {}", path.display(), e);
+    }
+  }
+
+  pub fn entries(&self) -> &[HighScoreEntry] {
+    &self.entries
+  }
+
+  pub fn best(&self) -> usize {
+    self.entries.first().map_or(0, |entry| entry.score)
+  }
+
+  pub fn qualifies(&self, score: usize) -> bool {
+    score > 0
+      && (self.entries.len() < HIGH_SCORE_COUNT
+        || self.entries.last().is_some_and(|lowest| score > lowest.score))
+  }
+
+  fn insert(&mut self, entry: HighScoreEntry) {
+    // Ties go to the earlier run.
+    let index = self.entries.iter().position(|other| entry.score > other.score).unwrap_or(self.entries.len());
+
+    self.entries.insert(index, entry);
+    self.entries.truncate(HIGH_SCORE_COUNT);
+    self.save();
+  }
+}
+
+// The high score shown in play starts from the best run in the table.
+fn seed_high_score(high_scores: Res<HighScores>, mut game: ResMut<Game>) {
+  game.raise_high_score(high_scores.best());
+}
+
+// Initials typed so far on the name entry screen.
+#[derive(Default, Resource, Deref, DerefMut)]
+struct Initials(String);
+
+fn clear_initials(mut initials: ResMut<Initials>) {
+  initials.clear();
+}
+
+// Read from key presses rather than text input so recordings capture them.
+fn letter(key: KeyCode) -> Option<char> {
+  // KeyCode declares the letters in order.
+  (KeyCode::A..=KeyCode::Z).contains(&key).then(|| (b'A' + (key as u32 - KeyCode::A as u32) as u8) as char)
+}
+
+// Letters are cycled with the d-pad on a gamepad.
+fn cycle_letter(letter: char, step: i8) -> char {
+  let index = (letter as u8 - b'A') as i8;
+  (b'A' + (index + step).rem_euclid(26) as u8) as char
+}
+
+fn enter_initials(
+  keyboard_input: Res<Input<KeyCode>>,
+  pad_buttons: Res<Input<GamepadButton>>,
+  mut initials: ResMut<Initials>,
+  mut high_scores: ResMut<HighScores>,
+  mut next_state: ResMut<NextState<GameState>>,
+  game: Res<Game>,
+  score: Res<Score>,
+  rng: Res<GameRng>,
+) {
+  for key in keyboard_input.get_just_pressed() {
+    if let Some(letter) = letter(*key) {
+      if initials.len() < MAX_INITIALS {
+        initials.push(letter);
+      }
+    }
+  }
+
+  for button in pad_buttons.get_just_pressed() {
+    match button.button_type {
+      GamepadButtonType::DPadRight if initials.len() < MAX_INITIALS => initials.push('A'),
+      GamepadButtonType::DPadUp | GamepadButtonType::DPadDown => {
+        let step = if button.button_type == GamepadButtonType::DPadUp { 1 } else { -1 };
+
+        match initials.pop() {
+          Some(letter) => initials.push(cycle_letter(letter, step)),
+          None => initials.push('A'),
+        }
+      },
+      _ => (),
+    }
+  }
+
+  let pad_just_pressed = |pad_button: GamepadButtonType| pad_buttons.get_just_pressed().any(|button| button.button_type == pad_button);
+
+  if keyboard_input.just_pressed(KeyCode::Back) || pad_just_pressed(GamepadButtonType::East) {
+    initials.pop();
+  }
+
+  let confirmed = keyboard_input.just_pressed(KeyCode::Return) || pad_just_pressed(GamepadButtonType::Start);
+
+  if confirmed && !initials.is_empty() {
+    high_scores.insert(HighScoreEntry {
+      initials: initials.clone(),
+      score: **score,
+      wave: game.wave_reached(),
+      date: today(),
+      seed: rng.seed(),
+    });
+    next_state.set(GameState::Splash);
+  }
+}
+
+fn today() -> String {
+  let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or_default();
+  let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 to a Gregorian calendar date.
+// See https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+  let month = (if month_index < 10 { month_index + 3 } else { month_index - 9 }) as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  (year, month, day)
+}
+
+// Tag component used to tag entities added on the name entry screen
+#[derive(Component)]
+struct OnNameEntryScreen;
+
+#[derive(Component)]
+struct ScreenText;
+
+fn spawn_centered_text(commands: &mut Commands, marker: impl Component, text: String) {
+  commands
+      .spawn((
+          NodeBundle {
+              style: Style {
+                  position_type: PositionType::Absolute,
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  width: Val::Percent(100.0),
+                  height: Val::Percent(100.0),
+                  ..default()
+              },
+              background_color: TABLE_BACKGROUND_COLOR.into(),
+              ..default()
+          },
+          marker,
+      ))
+      .with_children(|parent| {
+          parent.spawn((
+              ScreenText,
+              TextBundle::from_section(
+                  text,
+                  TextStyle {
+                      font_size: TABLE_FONT_SIZE,
+                      color: TABLE_TEXT_COLOR,
+                      ..default()
+                  },
+              )
+              .with_text_alignment(TextAlignment::Center),
+          ));
+      });
+}
+
+fn name_entry_setup(mut commands: Commands) {
+  spawn_centered_text(&mut commands, OnNameEntryScreen, String::new());
+}
+
+fn update_name_entry(
+  initials: Res<Initials>,
+  score: Res<Score>,
+  mut text: Query<&mut Text, With<ScreenText>>,
+) {
+  let mut text = text.single_mut();
+  let blanks = "_".repeat(MAX_INITIALS - initials.len());
+
+  text.sections[0].value = format!(
+    "New high score: {}!\nEnter your initials\n{}{}\nPress Enter (Start) when done",
+    **score, **initials, blanks
+  );
+}
+
+// Tag component used to tag the high-score table shown over the splash screen
+#[derive(Component)]
+struct OnHighScoreTable;
+
+fn toggle_high_score_table(
+  mut commands: Commands,
+  actions: Actions,
+  high_scores: Res<HighScores>,
+  table: Query<Entity, With<OnHighScoreTable>>,
+) {
+  if !actions.just_pressed(Action::HighScores) {
+    return;
+  }
+
+  if let Ok(entity) = table.get_single() {
+    commands.entity(entity).despawn_recursive();
+    return;
+  }
+
+  let mut text = String::from("High scores\n\n");
+
+  if high_scores.entries().is_empty() {
+    text.push_str("No runs yet\n");
+  }
+
+  for (rank, entry) in high_scores.entries().iter().enumerate() {
+    text.push_str(&format!(
+      "{:>2}. {:<3} {:>6}  wave {:>3}  {}  seed {}\n",
+      rank + 1, entry.initials, entry.score, entry.wave, entry.date, entry.seed
+    ));
+  }
+
+  text.push_str(&format!("\nPress {} to close", actions.describe(Action::HighScores)));
+
+  spawn_centered_text(&mut commands, OnHighScoreTable, text);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(initials: &str, score: usize) -> HighScoreEntry {
+    HighScoreEntry { initials: initials.into(), score, wave: 1, date: "2024-01-01".into(), seed: 0 }
+  }
+
+  fn initials(high_scores: &HighScores) -> Vec<&str> {
+    high_scores.entries().iter().map(|entry| entry.initials.as_str()).collect()
+  }
+
+  #[test]
+  fn keeps_the_best_runs_first_with_ties_going_to_the_earlier_one() {
+    let mut high_scores = HighScores { entries: Vec::new(), path: None };
+
+    high_scores.insert(entry("BOB", 200));
+    high_scores.insert(entry("AMY", 500));
+    high_scores.insert(entry("CAT", 200));
+    high_scores.insert(entry("DAN", 300));
+
+    assert_eq!(initials(&high_scores), vec!["AMY", "DAN", "BOB", "CAT"]);
+    assert_eq!(high_scores.best(), 500);
+  }
+
+  #[test]
+  fn keeps_only_the_top_ten() {
+    let mut high_scores = HighScores { entries: Vec::new(), path: None };
+
+    for score in 1..=HIGH_SCORE_COUNT {
+      assert!(high_scores.qualifies(score * 100));
+      high_scores.insert(entry("AAA", score * 100));
+    }
+
+    assert!(!high_scores.qualifies(100));
+    assert!(high_scores.qualifies(150));
+
+    high_scores.insert(entry("NEW", 150));
+
+    assert_eq!(high_scores.entries().len(), HIGH_SCORE_COUNT);
+    assert_eq!(high_scores.entries().last().map(|entry| entry.score), Some(150));
+  }
+
+  #[test]
+  fn empty_runs_never_qualify() {
+    let high_scores = HighScores { entries: Vec::new(), path: None };
+
+    assert!(!high_scores.qualifies(0));
+    assert_eq!(high_scores.best(), 0);
+  }
+
+  #[test]
+  fn converts_days_to_dates() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(59), (1970, 3, 1));
+    assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    assert_eq!(civil_from_days(19_722), (2023, 12, 31));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+  }
+
+  #[test]
+  fn reads_letters_from_their_keys() {
+    assert_eq!(letter(KeyCode::A), Some('A'));
+    assert_eq!(letter(KeyCode::M), Some('M'));
+    assert_eq!(letter(KeyCode::Z), Some('Z'));
+    assert_eq!(letter(KeyCode::Key1), None);
+    assert_eq!(letter(KeyCode::Return), None);
+  }
+
+  #[test]
+  fn records_the_last_wave_reached() {
+    let mut game = Game::default();
+    game.resume(9, 1, 3, 0);
+    game.start_wave();
+    assert_eq!(game.wave_reached(), 10);
+
+    // Clearing the campaign's last wave moves the wave number past it.
+    game.clear_wave();
+    assert_eq!(game.wave_number(), 10);
+    assert_eq!(game.wave_reached(), 10);
+
+    game.start_wave();
+    assert_eq!(game.wave_reached(), 11);
+  }
+}
@@ -1,58 +1,156 @@
+use actions::{ActionsPlugin, RebindScreenPlugin};
 use audio::GameAudioPlugin;
-use bevy::{asset::AssetMetaCheck, prelude::*};
+use banner::BannerPlugin;
+use behaviour::{BehaviourDebugPlugin, BehaviourPlugin};
+use bevy::{app::PluginGroupBuilder, asset::AssetMetaCheck, input::InputPlugin, prelude::*};
 use bevy_ecs_ldtk::prelude::*;
+use boss::{BossBarPlugin, BossPlugin};
+use campaign::CampaignPlugin;
 use combat::CombatPlugin;
+use enemies::EnemiesPlugin;
 use events::EventPlugin;
-use game::{GamePlugin, InGameSet};
+use game::{GamePlugin, InGameSet, RequiredAssets};
+use headless::HeadlessPlugin;
+use highscores::{HighScoresPlugin, HighScoresScreenPlugin};
 use nowalk::NoWalkPlugin;
+use options::LaunchOptions;
+use pathfinding::PathfindingPlugin;
 use player::{Player, PlayerPlugin};
-use player_movement::PlayerMovementPlugin;
+use projectile::{ProjectilePlugin, ProjectileSpritePlugin};
+use movement::MovementPlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
+use save::SavePlugin;
 use score::ScorePlugin;
+use spawn_points::SpawnPointsPlugin;
 use spawner::SpawnerPlugin;
-use sprite::SpritePlugin;
+use splash::SplashPlugin;
+use sprite::{SpriteAnimationPlugin, SpritePlugin};
+use waves::WavesPlugin;
 
 // AXOL
+mod actions;
 mod audio;
 mod axol;
+mod banner;
+mod behaviour;
+mod boss;
+mod campaign;
+mod collision;
 mod combat;
+mod enemies;
 mod events;
 mod game;
+mod headless;
+mod highscores;
+mod movement;
 mod nowalk;
+mod options;
+mod pathfinding;
 mod player;
-mod player_movement;
+mod projectile;
+mod replay;
+mod ron_asset;
+mod rng;
+mod save;
 mod score;
+mod spawn_points;
 mod spawner;
+mod splash;
 mod sprite;
+mod waves;
 
+// Everything needed to play the game, with no window, renderer or audio.
+struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+  fn build(self) -> PluginGroupBuilder {
+    PluginGroupBuilder::start::<Self>()
+      .add(GamePlugin)
+      .add(EventPlugin)
+      .add(ActionsPlugin)
+      .add(RngPlugin)
+      .add(PlayerPlugin)
+      .add(MovementPlugin)
+      .add(ProjectilePlugin)
+      .add(PathfindingPlugin)
+      .add(SpriteAnimationPlugin)
+      .add(EnemiesPlugin)
+      .add(CombatPlugin)
+      .add(BehaviourPlugin)
+      .add(BossPlugin)
+      .add(NoWalkPlugin)
+      .add(SpawnPointsPlugin)
+      .add(SpawnerPlugin)
+      .add(WavesPlugin)
+      .add(CampaignPlugin)
+      .add(SavePlugin)
+      .add(HighScoresPlugin)
+  }
+}
+
+// Drawing, sound and screens layered on top of the simulation.
+struct PresentationPlugins;
+
+impl PluginGroup for PresentationPlugins {
+  fn build(self) -> PluginGroupBuilder {
+    PluginGroupBuilder::start::<Self>()
+      .add(SplashPlugin)
+      .add(SpritePlugin)
+      .add(ProjectileSpritePlugin)
+      .add(GameAudioPlugin)
+      .add(ScorePlugin)
+      .add(BossBarPlugin)
+      .add(BannerPlugin)
+      .add(HighScoresScreenPlugin)
+      .add(RebindScreenPlugin)
+      .add(BehaviourDebugPlugin)
+  }
+}
 
 fn main() {
-    App::new()
-        .insert_resource(AssetMetaCheck::Never)
-        .add_plugins((DefaultPlugins.set(ImagePlugin::default_nearest()).set(WindowPlugin {
-            primary_window: Some(Window {
-                // NOTE: Doesn't work in bevy 0.13 but should in 0.14..
-                fit_canvas_to_parent: true,
-                prevent_default_event_handling: false,
-                ..default()
-            }),
-            ..default()
-        }), LdtkPlugin))
-        .add_plugins((GamePlugin, EventPlugin, PlayerPlugin, PlayerMovementPlugin, SpritePlugin, GameAudioPlugin, CombatPlugin, NoWalkPlugin, ScorePlugin, SpawnerPlugin))
-        .insert_resource(LevelSelection::index(0))
-        .add_systems(Startup, setup)
-        .add_systems(Update, (camera_follow_player).in_set(InGameSet::Camera))
-        .run();
+    build_app(LaunchOptions::from_args()).run();
+}
+
+fn build_app(options: LaunchOptions) -> App {
+    let mut app = App::new();
+
+    app.insert_resource(options.clone());
+
+    if options.headless {
+      app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin, HeadlessPlugin));
+    } else {
+      app.insert_resource(AssetMetaCheck::Never)
+         .add_plugins((DefaultPlugins.set(ImagePlugin::default_nearest()).set(WindowPlugin {
+              primary_window: Some(Window {
+                  // NOTE: Doesn't work in bevy 0.13 but should in 0.14..
+                  fit_canvas_to_parent: true,
+                  prevent_default_event_handling: false,
+                  ..default()
+              }),
+              ..default()
+          }), LdtkPlugin))
+         .add_plugins(PresentationPlugins)
+         .add_systems(Startup, setup)
+         .add_systems(Update, (camera_follow_player).in_set(InGameSet::Camera));
+    }
+
+    app.add_plugins((SimulationPlugins, ReplayPlugin));
+    app
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut required: ResMut<RequiredAssets>) {
     let mut camera = Camera2dBundle::default();
     camera.projection.scale = 0.40;
     camera.transform.translation.x += 1280.0 / 4.0;
     camera.transform.translation.y += 720.0 / 4.0;
     commands.spawn(camera);
 
+    let ldtk_handle = asset_server.load("axol.ldtk");
+    required.add(&ldtk_handle);
+
     commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("axol.ldtk"),
+        ldtk_handle,
         ..Default::default()
     });
 }
@@ -67,3 +165,30 @@ fn camera_follow_player(
     camera_transform.translation.x = player_transform.translation.x;
     camera_transform.translation.y = player_transform.translation.y;
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::AppExit;
+    use crate::game::Game;
+    use super::*;
+
+    // Plays the real game headless, as CI does, and checks the autopilot gets
+    // somewhere rather than only that nothing panicked.
+    #[test]
+    fn autopilot_clears_waves_headless() {
+        let mut app = build_app(LaunchOptions {
+            headless: true,
+            max_frames: Some(36000),
+            seed: Some(1),
+            ..default()
+        });
+        app.finish();
+        app.cleanup();
+
+        while app.world.resource::<Events<AppExit>>().is_empty() {
+            app.update();
+        }
+
+        assert!(app.world.resource::<Game>().wave_number() > 0);
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{collision::{move_and_slide, Collider}, game::InGameSet, nowalk::LevelNoWalk};
+
+// Radians between the directions bodies in the same spot are split up in, so
+// no two of them go the same way.
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(FixedUpdate, (apply_move_intent, separate_bodies, integrate_velocity).chain().in_set(InGameSet::Movement));
+  }
+}
+
+// Where an entity wants to go this tick. Set from input for the player and by
+// the AI for axols.
+#[derive(Default, Component, Clone, Copy)]
+pub struct MoveIntent {
+  pub direction: Vec2,
+  pub running: bool,
+}
+
+// Pixels per second.
+#[derive(Default, Component, Clone, Copy)]
+pub struct MoveSpeed {
+  pub walk: f32,
+  pub run: f32,
+}
+
+// Pixels per second.
+#[derive(Default, Component, Clone, Copy, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+// The way an entity last moved, as a unit vector. Sprites and attacks go by
+// this.
+#[derive(Component, Clone, Copy, Deref, DerefMut)]
+pub struct Facing(pub Vec2);
+
+impl Default for Facing {
+  fn default() -> Self {
+    Facing(Vec2::X)
+  }
+}
+
+// Room a body takes up among others. Bodies that can be pushed are steered
+// out of any others they overlap, and the push goes through the same
+// collision as the rest of their movement so it never ends up in NoWalk.
+#[derive(Default, Component, Clone, Copy, Deserialize)]
+pub struct Separation {
+  pub radius: f32,
+  // Pixels per second when fully overlapped, 0 for bodies that hold their
+  // ground.
+  pub push: f32,
+}
+
+#[derive(Default, Bundle)]
+pub struct MoverBundle {
+  pub intent: MoveIntent,
+  pub speed: MoveSpeed,
+  pub velocity: Velocity,
+  pub facing: Facing,
+}
+
+impl MoverBundle {
+  pub fn new(walk: f32, run: f32) -> Self {
+    MoverBundle {
+      speed: MoveSpeed { walk, run },
+      ..default()
+    }
+  }
+}
+
+// Intents up to unit length move at that fraction of full speed, for analog
+// sticks. Longer ones, like diagonals, are cut down to full speed.
+fn apply_move_intent(
+  mut movers: Query<(&MoveIntent, &MoveSpeed, &mut Velocity, &mut Facing)>
+) {
+  for (intent, speed, mut velocity, mut facing) in movers.iter_mut() {
+    let speed = if intent.running { speed.run } else { speed.walk };
+
+    velocity.0 = intent.direction.clamp_length_max(1.) * speed;
+
+    if let Some(direction) = intent.direction.try_normalize() {
+      facing.0 = direction;
+    }
+  }
+}
+
+// Adds a push away from every overlapping body, stronger the deeper the
+// overlap, on top of the velocity from the intent.
+fn separate_bodies(
+  mut movers: Query<(Entity, &Transform, &Separation, &mut Velocity)>,
+  bodies: Query<(Entity, &Transform, &Separation)>,
+) {
+  for (entity, transform, separation, mut velocity) in movers.iter_mut() {
+    if separation.push <= 0. {
+      continue;
+    }
+
+    let position = transform.translation.xy();
+    let mut push = Vec2::ZERO;
+
+    for (other, other_transform, other_separation) in bodies.iter() {
+      let reach = separation.radius + other_separation.radius;
+      let away = position - other_transform.translation.xy();
+      let distance = away.length();
+
+      if other == entity || distance >= reach {
+        continue;
+      }
+
+      let direction = away.try_normalize()
+        .unwrap_or_else(|| Vec2::from_angle(entity.index() as f32 * GOLDEN_ANGLE));
+
+      push += direction * (1. - distance / reach);
+    }
+
+    velocity.0 += push.clamp_length_max(1.) * separation.push;
+  }
+}
+
+fn integrate_velocity(
+  time: Res<Time>,
+  level_nowalk: Res<LevelNoWalk>,
+  mut movers: Query<(&mut Transform, &Velocity, Option<&Collider>)>
+) {
+  for (mut transform, velocity, collider) in movers.iter_mut() {
+    if velocity.0 == Vec2::ZERO {
+      continue;
+    }
+
+    let collider = collider.copied().unwrap_or_default();
+    let position = move_and_slide(&level_nowalk, &collider, transform.translation.xy(), velocity.0 * time.delta_seconds());
+
+    transform.translation = position.extend(transform.translation.z);
+  }
+}
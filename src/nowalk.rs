@@ -1,19 +1,28 @@
 use bevy::prelude::*;
-use bevy_ecs_ldtk::{app::LdtkIntCellAppExt, assets::{LdtkProject, LevelMetadataAccessor}, GridCoords, LdtkIntCell, LevelEvent};
+use bevy_ecs_ldtk::{app::LdtkIntCellAppExt, assets::{LdtkProject, LevelIndices, LevelMetadataAccessor}, ldtk::{LdtkJson, Level}, utils::int_grid_index_to_grid_coords, GridCoords, LdtkIntCell, LdtkPlugin, LevelEvent, LevelSelection};
 use std::collections::HashSet;
 
 use crate::sprite::AtlasHandles;
 
 pub const GRID_SIZE: i32 = 32;
+const NOWALK_INT_CELL: i32 = 2;
+
+// Read directly when running without the LDtk plugin (headless).
+pub const LDTK_PROJECT_PATH: &str = "assets/axol.ldtk";
 
 pub struct NoWalkPlugin;
 
 impl Plugin for NoWalkPlugin {
   fn build(&self, app: &mut App) {
-      app.register_ldtk_int_cell::<NoWalkBundle>(2)
-         .init_resource::<LevelNoWalk>()
-         .add_systems(Startup,  (cache_nowalk_locations).run_if(resource_exists::<AtlasHandles>()))
-         .add_systems(Update, cache_nowalk_locations);
+      app.init_resource::<LevelNoWalk>();
+
+      if app.is_plugin_added::<LdtkPlugin>() {
+        app.register_ldtk_int_cell::<NoWalkBundle>(NOWALK_INT_CELL)
+           .add_systems(Startup,  (cache_nowalk_locations).run_if(resource_exists::<AtlasHandles>()))
+           .add_systems(Update, cache_nowalk_locations);
+      } else {
+        app.add_systems(Update, (load_nowalk_from_project_file).run_if(resource_exists_and_changed::<LevelSelection>()));
+      }
   }
 }
 
@@ -34,6 +43,19 @@ pub struct LevelNoWalk {
 }
 
 impl LevelNoWalk {
+    pub fn level_width(&self) -> i32 {
+        self.level_width
+    }
+
+    pub fn level_height(&self) -> i32 {
+        self.level_height
+    }
+
+    #[cfg(test)]
+    pub fn new(level_width: i32, level_height: i32, nowalk_locations: HashSet<GridCoords>) -> Self {
+        LevelNoWalk { nowalk_locations, level_width, level_height }
+    }
+
     pub fn in_nowalk(&self, grid_coords: &GridCoords) -> bool {
         grid_coords.x < 0
             || grid_coords.y < 0
@@ -70,4 +92,47 @@ fn cache_nowalk_locations(
             *level_nowalk = new_level_nowalk;
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn read_ldtk_project_file() -> LdtkJson {
+    let contents = std::fs::read_to_string(LDTK_PROJECT_PATH)
+        .unwrap_or_else(|e| panic!("Unable to read {}: {}", LDTK_PROJECT_PATH, e));
+
+    serde_json::from_str(&contents)
+        .unwrap_or_else(|e| panic!("Unable to parse {}: {}", LDTK_PROJECT_PATH, e))
+}
+
+// The level the LDtk plugin would spawn for this selection.
+pub fn selected_level<'a>(ldtk_json: &'a LdtkJson, selection: &LevelSelection) -> &'a Level {
+    ldtk_json.levels.iter()
+        .enumerate()
+        .find(|(index, level)| selection.is_match(&LevelIndices::in_root(*index), level))
+        .map(|(_, level)| level)
+        .unwrap_or_else(|| panic!("No level in {} matches {:?}", LDTK_PROJECT_PATH, selection))
+}
+
+fn load_nowalk_from_project_file(
+    mut level_nowalk: ResMut<LevelNoWalk>,
+    selection: Res<LevelSelection>,
+) {
+    let ldtk_json = read_ldtk_project_file();
+    let level = selected_level(&ldtk_json, &selection);
+
+    let mut nowalk_locations = HashSet::new();
+
+    for layer in level.layer_instances.iter().flatten() {
+        for (index, value) in layer.int_grid_csv.iter().enumerate() {
+            if *value == NOWALK_INT_CELL {
+                if let Some(grid_coords) = int_grid_index_to_grid_coords(index, layer.c_wid as u32, layer.c_hei as u32) {
+                    nowalk_locations.insert(grid_coords);
+                }
+            }
+        }
+    }
+
+    *level_nowalk = LevelNoWalk {
+        nowalk_locations,
+        level_width: level.px_wid / GRID_SIZE,
+        level_height: level.px_hei / GRID_SIZE,
+    };
+}
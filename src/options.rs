@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use std::{path::PathBuf, str::FromStr};
+
+// Settings picked up from the command line at launch.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct LaunchOptions {
+  pub headless: bool,
+  pub max_frames: Option<u32>,
+  pub seed: Option<u64>,
+  pub record: Option<PathBuf>,
+  pub replay: Option<PathBuf>,
+  pub flow_field: bool,
+  pub gamepad: bool,
+}
+
+impl LaunchOptions {
+  // A flag given a value it can't use stops the game before it starts rather
+  // than running with something other than what was asked for.
+  pub fn from_args() -> Self {
+    Self::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+      eprintln!("{}", e);
+      std::process::exit(2);
+    })
+  }
+
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+    let mut options = LaunchOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--headless" => options.headless = true,
+        "--frames" => options.max_frames = Some(value(&arg, args.next())?),
+        "--seed" => options.seed = Some(value(&arg, args.next())?),
+        "--record" => options.record = Some(value(&arg, args.next())?),
+        "--replay" => options.replay = Some(value(&arg, args.next())?),
+        "--flow-field" => options.flow_field = true,
+        "--gamepad" => options.gamepad = true,
+        _ => eprintln!("Ignoring unknown argument {}", arg),
+      }
+    }
+
+    Ok(options)
+  }
+
+  // Headless runs and recordings must play the same whatever is stored on the
+  // machine, so only regular sessions read or write user data.
+  pub fn uses_user_data(&self) -> bool {
+    !self.headless && self.record.is_none() && self.replay.is_none()
+  }
+}
+
+fn value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+  let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+  value.parse().map_err(|_| format!("Invalid value {:?} for {}", value, flag))
+}
+
+// Where a file of the player's is kept, e.g. ~/.local/share/axol/<file>.
+pub fn user_data_path(file: &str) -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join("axol").join(file))
+}
+
+// Where a setting of the player's is kept, e.g. ~/.config/axol/<file>.
+pub fn user_config_path(file: &str) -> Option<PathBuf> {
+  dirs::config_dir().map(|dir| dir.join("axol").join(file))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<LaunchOptions, String> {
+    LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+  }
+
+  #[test]
+  fn reads_flags_and_their_values() {
+    let options = parse(&["--headless", "--frames", "600", "--seed", "42", "--record", "run.ron", "--flow-field"]).unwrap();
+
+    assert!(options.headless);
+    assert_eq!(options.max_frames, Some(600));
+    assert_eq!(options.seed, Some(42));
+    assert_eq!(options.record, Some(PathBuf::from("run.ron")));
+    assert!(options.replay.is_none());
+    assert!(options.flow_field);
+    assert!(!options.gamepad);
+    assert!(!options.uses_user_data());
+  }
+
+  #[test]
+  fn skips_unknown_arguments() {
+    let options = parse(&["--verbose", "--gamepad"]).unwrap();
+
+    assert_eq!(options.max_frames, None);
+    assert!(options.gamepad);
+    assert!(options.uses_user_data());
+  }
+
+  #[test]
+  fn rejects_bad_and_missing_values() {
+    assert_eq!(parse(&["--seed", "abc"]).unwrap_err(), "Invalid value \"abc\" for --seed");
+    assert_eq!(parse(&["--frames", "x"]).unwrap_err(), "Invalid value \"x\" for --frames");
+    assert_eq!(parse(&["--frames", "-1"]).unwrap_err(), "Invalid value \"-1\" for --frames");
+    assert_eq!(parse(&["--headless", "--frames"]).unwrap_err(), "--frames needs a value");
+    assert_eq!(parse(&["--replay"]).unwrap_err(), "--replay needs a value");
+  }
+
+  #[test]
+  fn no_arguments_is_a_regular_session() {
+    let options = parse(&[]).unwrap();
+
+    assert!(!options.headless);
+    assert!(options.uses_user_data());
+  }
+}
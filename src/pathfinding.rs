@@ -0,0 +1,411 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{utils::{grid_coords_to_translation, translation_to_grid_coords}, GridCoords};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{axol::Axol, behaviour::steer_enemies, combat::Health, game::InGameSet, nowalk::{LevelNoWalk, GRID_SIZE}, options::LaunchOptions, player::Player};
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+  fn build(&self, app: &mut App) {
+      let navigation = Navigation::from_options(app.world.get_resource::<LaunchOptions>());
+
+      app.insert_resource(navigation)
+         .init_resource::<NavGrid>()
+         .init_resource::<FlowField>()
+         .add_systems(FixedUpdate, (
+           build_nav_grid.run_if(resource_changed::<LevelNoWalk>()),
+           update_paths.run_if(resource_equals(Navigation::Paths)),
+           update_flow_field.run_if(resource_equals(Navigation::FlowField))
+         ).chain().before(steer_enemies).in_set(InGameSet::Combat));
+  }
+}
+
+// How axols find their way to the player. Paths runs A* per axol; the flow
+// field is one search toward the player shared by every axol, so it scales to
+// large hordes.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Navigation {
+  #[default]
+  Paths,
+  FlowField,
+}
+
+impl Navigation {
+  fn from_options(options: Option<&LaunchOptions>) -> Self {
+    match options {
+      Some(options) if options.flow_field => Navigation::FlowField,
+      _ => Navigation::Paths,
+    }
+  }
+}
+
+// Which cells of the level can be walked through, rebuilt whenever the
+// LevelNoWalk cache changes.
+#[derive(Default, Resource)]
+pub struct NavGrid {
+  width: i32,
+  height: i32,
+  walkable: Vec<bool>,
+}
+
+impl NavGrid {
+  pub fn from_nowalk(level_nowalk: &LevelNoWalk) -> Self {
+    let width = level_nowalk.level_width();
+    let height = level_nowalk.level_height();
+
+    let walkable = (0..height)
+      .flat_map(|y| (0..width).map(move |x| GridCoords::new(x, y)))
+      .map(|grid_coords| !level_nowalk.in_nowalk(&grid_coords))
+      .collect();
+
+    NavGrid { width, height, walkable }
+  }
+
+  pub fn index(&self, grid_coords: GridCoords) -> Option<usize> {
+    if grid_coords.x < 0 || grid_coords.y < 0 || grid_coords.x >= self.width || grid_coords.y >= self.height {
+      return None;
+    }
+    Some((grid_coords.y * self.width + grid_coords.x) as usize)
+  }
+
+  pub fn coords(&self, index: usize) -> GridCoords {
+    GridCoords::new(index as i32 % self.width, index as i32 / self.width)
+  }
+
+  pub fn is_walkable(&self, grid_coords: GridCoords) -> bool {
+    self.index(grid_coords).is_some_and(|index| self.walkable[index])
+  }
+
+  // Walkable cells around a cell with the cost of stepping to them. Diagonal
+  // steps can't cut the corner of a blocked cell.
+  pub fn neighbours(&self, grid_coords: GridCoords) -> impl Iterator<Item = (GridCoords, u32)> + '_ {
+    [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)]
+      .into_iter()
+      .filter_map(move |(dx, dy)| {
+        let next = GridCoords::new(grid_coords.x + dx, grid_coords.y + dy);
+
+        if !self.is_walkable(next) {
+          return None;
+        }
+
+        if dx != 0 && dy != 0 {
+          let clear = self.is_walkable(GridCoords::new(grid_coords.x + dx, grid_coords.y))
+            && self.is_walkable(GridCoords::new(grid_coords.x, grid_coords.y + dy));
+
+          return clear.then_some((next, DIAGONAL_COST));
+        }
+
+        Some((next, STRAIGHT_COST))
+      })
+  }
+}
+
+pub fn to_grid_coords(translation: Vec2) -> GridCoords {
+  translation_to_grid_coords(translation, IVec2::splat(GRID_SIZE))
+}
+
+pub fn to_translation(grid_coords: GridCoords) -> Vec2 {
+  grid_coords_to_translation(grid_coords, IVec2::splat(GRID_SIZE))
+}
+
+fn octile_distance(from: GridCoords, to: GridCoords) -> u32 {
+  let dx = (from.x - to.x).unsigned_abs();
+  let dy = (from.y - to.y).unsigned_abs();
+
+  STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+// A* from start to goal. The path leaves out the start cell and ends on the
+// goal; None when the goal can't be reached.
+pub fn find_path(nav_grid: &NavGrid, start: GridCoords, goal: GridCoords) -> Option<Vec<GridCoords>> {
+  let start_index = nav_grid.index(start)?;
+  let goal_index = nav_grid.index(goal)?;
+
+  if !nav_grid.walkable[goal_index] {
+    return None;
+  }
+  if start_index == goal_index {
+    return Some(Vec::new());
+  }
+
+  let mut cost = vec![u32::MAX; nav_grid.walkable.len()];
+  let mut came_from = vec![usize::MAX; nav_grid.walkable.len()];
+  let mut open = BinaryHeap::new();
+
+  cost[start_index] = 0;
+  open.push(Reverse((octile_distance(start, goal), start_index)));
+
+  while let Some(Reverse((estimate, index))) = open.pop() {
+    if index == goal_index {
+      let mut path = vec![goal];
+      let mut current = index;
+
+      while came_from[current] != start_index {
+        current = came_from[current];
+        path.push(nav_grid.coords(current));
+      }
+
+      path.reverse();
+      return Some(path);
+    }
+
+    let current = nav_grid.coords(index);
+
+    // Skip stale entries for cells that were reached more cheaply since.
+    if estimate > cost[index] + octile_distance(current, goal) {
+      continue;
+    }
+
+    for (next, step_cost) in nav_grid.neighbours(current) {
+      let next_index = nav_grid.index(next).expect("neighbours are inside the grid");
+      let next_cost = cost[index] + step_cost;
+
+      if next_cost < cost[next_index] {
+        cost[next_index] = next_cost;
+        came_from[next_index] = index;
+        open.push(Reverse((next_cost + octile_distance(next, goal), next_index)));
+      }
+    }
+  }
+
+  None
+}
+
+// The cells an axol still has to walk through to reach the player, kept until
+// the player moves to another cell.
+#[derive(Default, Component)]
+pub struct NavPath {
+  goal: Option<GridCoords>,
+  waypoints: Vec<GridCoords>,
+}
+
+impl NavPath {
+  // The next cell to head for once the axol is standing in `current`.
+  pub fn next_waypoint(&mut self, current: GridCoords) -> Option<GridCoords> {
+    if let Some(position) = self.waypoints.iter().position(|waypoint| *waypoint == current) {
+      self.waypoints.drain(..=position);
+    }
+    self.waypoints.first().copied()
+  }
+}
+
+// For every cell the neighbouring cell that is one step closer to the player,
+// rebuilt when the player moves to another cell.
+#[derive(Default, Resource)]
+pub struct FlowField {
+  goal: Option<GridCoords>,
+  width: i32,
+  next: Vec<Option<GridCoords>>,
+}
+
+impl FlowField {
+  pub fn toward(nav_grid: &NavGrid, goal: GridCoords) -> Self {
+    let mut distance = vec![u32::MAX; nav_grid.walkable.len()];
+    let mut next = vec![None; nav_grid.walkable.len()];
+    let mut open = BinaryHeap::new();
+
+    if let Some(goal_index) = nav_grid.index(goal) {
+      distance[goal_index] = 0;
+      open.push(Reverse((0, goal_index)));
+    }
+
+    // Dijkstra outward from the goal. Steps cost the same both ways, so the
+    // cell a search comes from is the next step back toward the goal.
+    while let Some(Reverse((cell_distance, index))) = open.pop() {
+      if cell_distance > distance[index] {
+        continue;
+      }
+
+      let current = nav_grid.coords(index);
+
+      for (neighbour, step_cost) in nav_grid.neighbours(current) {
+        let neighbour_index = nav_grid.index(neighbour).expect("neighbours are inside the grid");
+        let neighbour_distance = cell_distance + step_cost;
+
+        if neighbour_distance < distance[neighbour_index] {
+          distance[neighbour_index] = neighbour_distance;
+          next[neighbour_index] = Some(current);
+          open.push(Reverse((neighbour_distance, neighbour_index)));
+        }
+      }
+    }
+
+    FlowField { goal: Some(goal), width: nav_grid.width, next }
+  }
+
+  // None in the goal cell itself or where the goal can't be reached.
+  pub fn next_cell(&self, grid_coords: GridCoords) -> Option<GridCoords> {
+    if grid_coords.x < 0 || grid_coords.y < 0 || grid_coords.x >= self.width {
+      return None;
+    }
+    let index = (grid_coords.y * self.width + grid_coords.x) as usize;
+    self.next.get(index).copied().flatten()
+  }
+}
+
+fn build_nav_grid(
+  mut nav_grid: ResMut<NavGrid>,
+  level_nowalk: Res<LevelNoWalk>,
+) {
+  *nav_grid = NavGrid::from_nowalk(&level_nowalk);
+}
+
+fn update_paths(
+  nav_grid: Res<NavGrid>,
+  player: Query<&Transform, With<Player>>,
+  mut axols: Query<(&Transform, &Health, &mut NavPath), With<Axol>>,
+) {
+  let Ok(player_transform) = player.get_single() else {
+    return;
+  };
+  let goal = to_grid_coords(player_transform.translation.xy());
+
+  for (transform, health, mut path) in axols.iter_mut() {
+    if health.0 <= 0 {
+      continue;
+    }
+
+    if path.goal == Some(goal) && !nav_grid.is_changed() {
+      continue;
+    }
+
+    let start = to_grid_coords(transform.translation.xy());
+
+    *path = NavPath {
+      goal: Some(goal),
+      waypoints: find_path(&nav_grid, start, goal).unwrap_or_default(),
+    };
+  }
+}
+
+fn update_flow_field(
+  nav_grid: Res<NavGrid>,
+  mut flow_field: ResMut<FlowField>,
+  player: Query<&Transform, With<Player>>,
+) {
+  let Ok(player_transform) = player.get_single() else {
+    return;
+  };
+  let goal = to_grid_coords(player_transform.translation.xy());
+
+  if flow_field.goal != Some(goal) || nav_grid.is_changed() {
+    *flow_field = FlowField::toward(&nav_grid, goal);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // One string per row, top row first, '#' for blocked cells.
+  fn grid(rows: &[&str]) -> NavGrid {
+    let height = rows.len() as i32;
+    let width = rows[0].len() as i32;
+
+    let walkable = (0..height)
+      .flat_map(|y| rows[(height - 1 - y) as usize].chars().map(|cell| cell != '#'))
+      .collect();
+
+    NavGrid { width, height, walkable }
+  }
+
+  fn path_cost(start: GridCoords, path: &[GridCoords]) -> u32 {
+    std::iter::once(start)
+      .chain(path.iter().copied())
+      .zip(path.iter())
+      .map(|(from, to)| octile_distance(from, *to))
+      .sum()
+  }
+
+  #[test]
+  fn finds_a_path_around_a_wall() {
+    let nav_grid = grid(&[
+      ".....",
+      ".###.",
+      ".....",
+    ]);
+    let start = GridCoords::new(2, 0);
+    let goal = GridCoords::new(2, 2);
+
+    let path = find_path(&nav_grid, start, goal).expect("goal is reachable");
+
+    assert_eq!(path.last(), Some(&goal));
+    assert!(path.iter().all(|cell| nav_grid.is_walkable(*cell)));
+    // Two steps out to the end of the wall, two up past it, two back in.
+    assert_eq!(path_cost(start, &path), 6 * STRAIGHT_COST);
+  }
+
+  #[test]
+  fn unreachable_goal_has_no_path() {
+    let nav_grid = grid(&[
+      ".....",
+      ".###.",
+      ".#.#.",
+      ".###.",
+    ]);
+
+    assert_eq!(find_path(&nav_grid, GridCoords::new(0, 0), GridCoords::new(2, 1)), None);
+    assert_eq!(find_path(&nav_grid, GridCoords::new(0, 0), GridCoords::new(1, 1)), None);
+  }
+
+  #[test]
+  fn diagonals_dont_cut_blocked_corners() {
+    let nav_grid = grid(&[
+      "..",
+      "#.",
+    ]);
+    let top_left = GridCoords::new(0, 1);
+    let top_right = GridCoords::new(1, 1);
+    let bottom_right = GridCoords::new(1, 0);
+
+    let neighbours: Vec<_> = nav_grid.neighbours(top_left).collect();
+    assert_eq!(neighbours, vec![(top_right, STRAIGHT_COST)]);
+
+    assert_eq!(find_path(&nav_grid, top_left, bottom_right), Some(vec![top_right, bottom_right]));
+
+    let open = grid(&[
+      "..",
+      "..",
+    ]);
+    assert!(open.neighbours(top_left).any(|neighbour| neighbour == (bottom_right, DIAGONAL_COST)));
+  }
+
+  #[test]
+  fn flow_field_agrees_with_a_star() {
+    let nav_grid = grid(&[
+      "......#...",
+      ".####.#.#.",
+      ".#....#.#.",
+      ".#.####.#.",
+      "...#......",
+    ]);
+    let goal = GridCoords::new(9, 4);
+    let flow_field = FlowField::toward(&nav_grid, goal);
+
+    for start in (0..nav_grid.walkable.len()).map(|index| nav_grid.coords(index)) {
+      if !nav_grid.is_walkable(start) {
+        continue;
+      }
+
+      let Some(path) = find_path(&nav_grid, start, goal) else {
+        assert_eq!(flow_field.next_cell(start), None, "no way from {:?}", start);
+        continue;
+      };
+
+      let mut current = start;
+      let mut cost = 0;
+      while let Some(next) = flow_field.next_cell(current) {
+        cost += octile_distance(current, next);
+        current = next;
+      }
+
+      assert_eq!(current, goal);
+      assert_eq!(cost, path_cost(start, &path), "from {:?}", start);
+    }
+  }
+}
@@ -1,21 +1,23 @@
 use bevy::prelude::*;
-use std::collections::HashMap;
-use crate::{combat::Health, events::{RunEvent, SwordSwingEvent, WalkEvent}, game::{GameState, InGameSet}, sprite::{AnimFrame, AnimState, AnimationDirection, AnimationIndices, AnimationTimer, AtlasHandles, MoveDir}};
-
-#[derive(Default, Component, PartialEq)]
-pub struct Moving(pub bool);
+use crate::{actions::{Action, Actions}, collision::Collider, combat::{Faction, Health, Strike}, events::SwordSwingEvent, game::{GameState, InGameSet}, movement::{MoveIntent, MoverBundle, Separation}, spawn_points::LevelSpawnPoints, sprite::{AnimFrame, AnimState, AnimationLibrary, AnimationSet, AnimationTimer, MoveDir, ReachedFrame}};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
   fn build(&self, app: &mut App) {
-      app.add_systems(OnExit(GameState::Splash), setup_player)
+      app.add_systems(OnTransition { from: GameState::Splash, to: GameState::InGame }, setup_player)
          .add_systems(Update,
            (handle_input).in_set(InGameSet::PlayerMovement)
           );
   }
 }
 
+// Pixels per second.
+const PLAYER_WALK_SPEED: f32 = 18.;
+const PLAYER_RUN_SPEED: f32 = 30.;
+// Axols keep this far off, but the player is never pushed.
+const PLAYER_SEPARATION_RADIUS: f32 = 16.;
+
 #[derive(Default, Component)]
 pub struct Player;
 
@@ -23,279 +25,84 @@ pub struct Player;
 pub struct PlayerBundle {
   player: Player,
   health: Health,
+  faction: Faction,
   amin_state: AnimState,
-  moving: Moving,
+  mover: MoverBundle,
+  collider: Collider,
+  separation: Separation,
+  strike: Strike,
   move_dir: MoveDir,
-  sprite_sheet: SpriteSheetBundle,
-  animation_indices: AnimationIndices,
+  transform: TransformBundle,
+  animations: Handle<AnimationSet>,
   anim_timer: AnimationTimer,
-  anim_frame: AnimFrame
+  anim_frame: AnimFrame,
+  reached_frame: ReachedFrame
 }
 
 pub fn setup_player(
   mut commands: Commands,
-  atlas_handles: Res<AtlasHandles>,
+  animation_library: Res<AnimationLibrary>,
+  spawn_points: Res<LevelSpawnPoints>,
 ) {
-  let sprite_player = TextureAtlasSprite {
-    index: 0,
-    ..default()
-  };
-
   commands.spawn(
     PlayerBundle {
       player: Player,
       health: Health(20, 20),
+      faction: Faction::Player,
       amin_state: AnimState::Idle,
-      moving: Moving(false),
+      mover: MoverBundle::new(PLAYER_WALK_SPEED, PLAYER_RUN_SPEED),
+      collider: Collider::new(28., 40.),
+      separation: Separation { radius: PLAYER_SEPARATION_RADIUS, push: 0. },
+      strike: Strike::default(),
       move_dir: MoveDir::Right,
-      sprite_sheet: SpriteSheetBundle {
-        sprite: sprite_player,
-        texture_atlas: atlas_handles.handles[0].clone(),
-        transform: Transform {
-          translation: Vec3 { x: 500., y: 500., z: 10.},
-          ..default()
-        },
-        ..default()
-      },
-      animation_indices: setup_player_animations(),
+      transform: TransformBundle::from_transform(
+        Transform::from_translation(spawn_points.player_start.extend(10.))
+      ),
+      animations: animation_library.player.clone(),
       anim_timer: AnimationTimer(Timer::from_seconds(0.20, TimerMode::Repeating)),
-      anim_frame: AnimFrame(0)
+      anim_frame: AnimFrame(0),
+      reached_frame: ReachedFrame::default()
     }
   );
 }
 
 pub fn handle_input(
-  key: Res<Input<KeyCode>>,
-  mouse: Res<Input<MouseButton>>,
-  mut walk_event: EventWriter<WalkEvent>,
-  mut run_event: EventWriter<RunEvent>,
+  actions: Actions,
   mut sword_event: EventWriter<SwordSwingEvent>,
-  mut player: Query<(Entity, &mut Moving, &mut MoveDir, &mut AnimState, &mut AnimFrame, &Health), With<Player>>
+  mut player: Query<(&mut MoveIntent, &mut AnimState, &mut AnimFrame, &mut Strike, &Health), With<Player>>
 ) {
-  let (entity, mut moving, mut move_dir, mut anim_state, mut anim_frame, health) = player.get_single_mut().expect("player not spawned");
-  let mut dir_facing = *move_dir;
-  let mut is_moving = false;
-  let shift = key.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+  let (mut intent, mut anim_state, mut anim_frame, mut strike, health) = player.get_single_mut().expect("player not spawned");
+  let mut direction = Vec2::ZERO;
+  let shift = actions.pressed(Action::Run);
 
   if *anim_state == AnimState::Dead {
+    *intent = MoveIntent::default();
     return;
   }
 
-  if key.just_pressed(KeyCode::Space) || mouse.just_pressed(MouseButton::Left) {
-    is_moving = false;
+  if actions.just_pressed(Action::Attack) {
     *anim_state = AnimState::Attack;
     anim_frame.0 = 0;
+    strike.start();
     sword_event.send_default();
   } else if *anim_state != AnimState::Attack {
-    if key.pressed(KeyCode::Up) {
-      dir_facing = MoveDir::Up;
-      is_moving = true;
-    } else if key.pressed(KeyCode::Down) {
-      dir_facing = MoveDir::Down;
-      is_moving = true;
-    } else if key.pressed(KeyCode::Left) {
-      dir_facing = MoveDir::Left;
-      is_moving = true;
-    } else if key.pressed(KeyCode::Right) {
-      dir_facing = MoveDir::Right;
-      is_moving = true;
-    }
-    if is_moving {
+    direction = actions.movement();
+
+    if direction != Vec2::ZERO {
       if shift {
         *anim_state = AnimState::Run;
-        run_event.send(RunEvent{ direction: dir_facing });
       } else {
         *anim_state = AnimState::Walk;
-        walk_event.send(WalkEvent{ direction: dir_facing, entity });
-      }
-    } else if *anim_state != AnimState::Attack {
-      if health.0 < health.1 as i8 {
-        *anim_state = AnimState::IdleInjured;
-      } else {
-        *anim_state = AnimState::Idle;
       }
+    } else if health.0 < health.1 as i32 {
+      *anim_state = AnimState::IdleInjured;
+    } else {
+      *anim_state = AnimState::Idle;
     }
   }
 
-  *moving = Moving(is_moving);
-  *move_dir = dir_facing;
-}
-
-pub fn setup_player_animations() -> AnimationIndices {
-  let mut animation_indices = AnimationIndices {
-    sheet_index: HashMap::new(),
-    animations: HashMap::new(),
-    timer_duration: HashMap::new()
+  *intent = MoveIntent {
+    direction,
+    running: shift,
   };
-
-  // IDLE
-  animation_indices.timer_duration.insert(AnimState::Idle, 0.15);
-  animation_indices.sheet_index.insert(AnimState::Idle, 0);
-  animation_indices.animations.insert((AnimState::Idle, MoveDir::Up), AnimationDirection {
-    frames : vec![30, 31, 32, 33, 34, 35, 36, 37, 38, 39],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Idle, MoveDir::Down), AnimationDirection {
-    frames : vec![20, 21, 22, 23, 24, 25, 26, 27, 28, 29],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Idle, MoveDir::Left), AnimationDirection {
-    frames : vec![10, 11, 12, 13, 14, 15, 16, 17, 18, 19],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Idle, MoveDir::Right), AnimationDirection {
-    frames : vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-
-  // IDLE Injured
-  animation_indices.timer_duration.insert(AnimState::IdleInjured, 0.15);
-  animation_indices.sheet_index.insert(AnimState::IdleInjured, 0);
-  animation_indices.animations.insert((AnimState::IdleInjured, MoveDir::Up), AnimationDirection {
-    frames : vec![70, 71, 72, 73, 74, 75, 76, 77, 78, 79],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::IdleInjured, MoveDir::Down), AnimationDirection {
-    frames : vec![60, 61, 62, 63, 64, 65, 66, 67, 68, 69],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::IdleInjured, MoveDir::Left), AnimationDirection {
-    frames : vec![50, 51, 52, 53, 54, 55, 56, 57, 58, 59],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::IdleInjured, MoveDir::Right), AnimationDirection {
-    frames : vec![40, 41, 42, 43, 44, 45, 46, 47, 48, 49],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-
-  // WALK
-  animation_indices.timer_duration.insert(AnimState::Walk, 0.1);
-  animation_indices.sheet_index.insert(AnimState::Walk, 1);
-  animation_indices.animations.insert((AnimState::Walk, MoveDir::Up), AnimationDirection {
-    frames : vec![36, 37, 38, 39, 40, 41, 42, 43],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Walk, MoveDir::Down), AnimationDirection {
-    frames : vec![24, 25, 26, 27, 28, 29, 30, 31],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Walk, MoveDir::Left), AnimationDirection {
-    frames : vec![12, 13, 14, 15, 16, 17, 18, 19],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Walk, MoveDir::Right), AnimationDirection {
-    frames : vec![0, 1, 2, 3, 4, 5, 6, 7],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-
-  // Run
-  animation_indices.timer_duration.insert(AnimState::Run, 0.1);
-  animation_indices.sheet_index.insert(AnimState::Run, 1);
-  animation_indices.animations.insert((AnimState::Run, MoveDir::Up), AnimationDirection {
-    frames : vec![44, 45, 46, 47],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Run, MoveDir::Down), AnimationDirection {
-    frames : vec![32, 33, 34, 35],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Run, MoveDir::Left), AnimationDirection {
-    frames : vec![20, 21, 22, 23],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-  animation_indices.animations.insert((AnimState::Run, MoveDir::Right), AnimationDirection {
-    frames : vec![8, 9, 10, 11],
-    flip_x: false,
-    flip_y: false,
-    looping: true
-  });
-
-  // ATTACK
-  animation_indices.timer_duration.insert(AnimState::Attack, 0.08);
-  animation_indices.sheet_index.insert(AnimState::Attack, 2);
-  animation_indices.animations.insert((AnimState::Attack, MoveDir::Up), AnimationDirection {
-    frames : vec![30, 31, 32, 33, 34, 35, 36, 37, 38, 39],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Attack, MoveDir::Down), AnimationDirection {
-    frames : vec![20, 21, 22, 23, 24, 25, 26, 27, 28, 29],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Attack, MoveDir::Left), AnimationDirection {
-    frames : vec![10, 11, 12, 13, 14, 15, 16, 17, 18, 19],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Attack, MoveDir::Right), AnimationDirection {
-    frames : vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-
-  // DYING
-  animation_indices.timer_duration.insert(AnimState::Dead, 0.2);
-  animation_indices.sheet_index.insert(AnimState::Dead, 3);
-  animation_indices.animations.insert((AnimState::Dead, MoveDir::Up), AnimationDirection {
-    frames : vec![0, 1, 2, 3],
-    flip_x: true,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Dead, MoveDir::Down), AnimationDirection {
-    frames : vec![0, 1, 2, 3],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Dead, MoveDir::Left), AnimationDirection {
-    frames : vec![0, 1, 2, 3],
-    flip_x: true,
-    flip_y: false,
-    looping: false
-  });
-  animation_indices.animations.insert((AnimState::Dead, MoveDir::Right), AnimationDirection {
-    frames : vec![0, 1, 2, 3],
-    flip_x: false,
-    flip_y: false,
-    looping: false
-  });
-
-  animation_indices
 }
-
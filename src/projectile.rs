@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{collision::Collider, combat::{Faction, Health}, events::{ProjectileHitEvent, SpawnProjectileEvent}, game::{GameState, InGameSet}, nowalk::{LevelNoWalk, GRID_SIZE}, pathfinding::to_grid_coords};
+
+const PLAYER_PROJECTILE_COLOR: Color = Color::rgb(0.9, 0.9, 0.8);
+const ENEMY_PROJECTILE_COLOR: Color = Color::rgb(0.5, 0.9, 0.3);
+
+// Moves projectiles, stops them at walls and hurts whatever they hit.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(FixedUpdate, (spawn_projectiles, move_projectiles, hit_targets).chain().in_set(InGameSet::Movement))
+         .add_systems(OnEnter(GameState::Splash), despawn_projectiles)
+         .add_systems(OnEnter(GameState::Restart), despawn_projectiles)
+         .add_systems(OnEnter(GameState::LevelTransition), despawn_projectiles)
+         .add_systems(OnEnter(GameState::Victory), despawn_projectiles);
+  }
+}
+
+// Draws projectiles as dots in their owner's colour.
+pub struct ProjectileSpritePlugin;
+
+impl Plugin for ProjectileSpritePlugin {
+  fn build(&self, app: &mut App) {
+      app.add_systems(Update, attach_projectile_sprites);
+  }
+}
+
+// What gets fired, so enemies and weapons can describe their own. Speed in
+// pixels per second, lifetime in seconds.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ProjectileStats {
+  pub speed: f32,
+  pub lifetime: f32,
+  pub damage: u8,
+  // Targets it goes through before stopping.
+  #[serde(default)]
+  pub pierce: u8,
+  pub radius: f32,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+  pub owner: Faction,
+  damage: u8,
+  pierce: u8,
+  radius: f32,
+  velocity: Vec2,
+  lifetime: Timer,
+  // Hit so far, so one that pierces can't hit the same target twice.
+  hit: Vec<Entity>,
+}
+
+fn spawn_projectiles(
+  mut commands: Commands,
+  mut events: EventReader<SpawnProjectileEvent>,
+) {
+  for event in events.read() {
+    let Some(direction) = event.direction.try_normalize() else {
+      continue;
+    };
+    let stats = event.stats;
+
+    commands.spawn((
+      Projectile {
+        owner: event.owner,
+        damage: stats.damage,
+        pierce: stats.pierce,
+        radius: stats.radius,
+        velocity: direction * stats.speed,
+        lifetime: Timer::from_seconds(stats.lifetime, TimerMode::Once),
+        hit: Vec::new(),
+      },
+      TransformBundle::from_transform(Transform::from_translation(event.position.extend(15.))),
+    ));
+  }
+}
+
+// Moves in steps of half a cell so a fast projectile can't skip over a wall.
+fn move_projectiles(
+  mut commands: Commands,
+  time: Res<Time>,
+  level_nowalk: Res<LevelNoWalk>,
+  mut projectiles: Query<(Entity, &mut Projectile, &mut Transform)>,
+) {
+  let max_step = GRID_SIZE as f32 / 2.;
+
+  for (entity, mut projectile, mut transform) in projectiles.iter_mut() {
+    if projectile.lifetime.tick(time.delta()).finished() {
+      commands.entity(entity).despawn_recursive();
+      continue;
+    }
+
+    let start = transform.translation.xy();
+    let motion = projectile.velocity * time.delta_seconds();
+    let steps = (motion.length() / max_step).ceil().max(1.);
+
+    let blocked = (1..=steps as u32)
+      .map(|step| start + motion * (step as f32 / steps))
+      .any(|position| level_nowalk.in_nowalk(&to_grid_coords(position)));
+
+    if blocked {
+      commands.entity(entity).despawn_recursive();
+    } else {
+      transform.translation = (start + motion).extend(transform.translation.z);
+    }
+  }
+}
+
+fn hit_targets(
+  mut commands: Commands,
+  mut projectiles: Query<(Entity, &mut Projectile, &Transform)>,
+  targets: Query<(Entity, &Transform, &Health, &Faction, Option<&Collider>)>,
+  mut hits: EventWriter<ProjectileHitEvent>,
+) {
+  for (entity, mut projectile, transform) in projectiles.iter_mut() {
+    let position = transform.translation.xy();
+
+    for (target, target_transform, health, faction, collider) in targets.iter() {
+      if *faction == projectile.owner || health.0 <= 0 || projectile.hit.contains(&target) {
+        continue;
+      }
+
+      let bounds = collider.copied().unwrap_or_default().bounds(target_transform.translation.xy());
+      if position.clamp(bounds.min, bounds.max).distance(position) > projectile.radius {
+        continue;
+      }
+
+      hits.send(ProjectileHitEvent { target, amount: projectile.damage });
+      projectile.hit.push(target);
+
+      if projectile.pierce == 0 {
+        commands.entity(entity).despawn_recursive();
+        break;
+      }
+      projectile.pierce -= 1;
+    }
+  }
+}
+
+fn despawn_projectiles(
+  mut commands: Commands,
+  projectiles: Query<Entity, With<Projectile>>,
+) {
+  for entity in projectiles.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+}
+
+fn attach_projectile_sprites(
+  mut commands: Commands,
+  projectiles: Query<(Entity, &Projectile), Without<Sprite>>,
+) {
+  for (entity, projectile) in projectiles.iter() {
+    let color = match projectile.owner {
+      Faction::Player => PLAYER_PROJECTILE_COLOR,
+      Faction::Enemy => ENEMY_PROJECTILE_COLOR,
+    };
+
+    commands.entity(entity).insert((
+      Sprite {
+        color,
+        custom_size: Some(Vec2::splat(projectile.radius * 2.)),
+        ..default()
+      },
+      Handle::<Image>::default(),
+      VisibilityBundle::default(),
+    ));
+  }
+}
@@ -0,0 +1,282 @@
+use bevy::{app::AppExit, input::{gamepad::GamepadEvent, InputSystem}, prelude::*, time::TimeUpdateStrategy, utils::Instant, window::{PresentMode, PrimaryWindow}};
+use serde::{Deserialize, Serialize};
+use std::{path::{Path, PathBuf}, time::Duration};
+
+use crate::{game::{GameState, SimulationSet}, options::LaunchOptions, pathfinding::Navigation, rng::GameRng};
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.;
+
+// Records every frame's input and time step with `--record <file>` and plays
+// them back with `--replay <file>`. During playback P pauses, . steps one
+// frame while paused and -/= change the playback speed.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+  fn build(&self, app: &mut App) {
+    let options = app.world.resource::<LaunchOptions>().clone();
+
+    if let Some(path) = options.replay {
+      let recording = Recording::load(&path)
+        .unwrap_or_else(|e| panic!("Unable to load replay {}: {}", path.display(), e));
+
+      app.insert_resource(GameRng::new(recording.fixed_seed, recording.session_seed))
+         .insert_resource(recording.navigation)
+         .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+         .insert_resource(Playback::new(recording, !options.headless))
+         .add_systems(Startup, uncap_frame_rate)
+         .add_systems(PreUpdate, (play_back_gamepad_events.before(InputSystem), play_back_input.after(InputSystem)))
+         .configure_sets(Update, SimulationSet.run_if(playback_advancing));
+    } else if let Some(path) = options.record {
+      app.insert_resource(Recorder { path, recording: Recording::default() })
+         .add_systems(Startup, start_recording)
+         .add_systems(Last, (record_frame.run_if(not(in_state(GameState::Loading))), save_recording_on_exit).chain());
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Recording {
+  fixed_seed: Option<u64>,
+  session_seed: u64,
+  #[serde(default)]
+  navigation: Navigation,
+  frames: Vec<RecordedFrame>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RecordedFrame {
+  delta: Duration,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pressed_keys: Vec<KeyCode>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  released_keys: Vec<KeyCode>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pressed_buttons: Vec<MouseButton>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  released_buttons: Vec<MouseButton>,
+  // Raw events, since gamepads come and go as well as press buttons.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  gamepad_events: Vec<GamepadEvent>,
+}
+
+impl Recording {
+  fn load(path: &Path) -> Result<Self, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+  }
+
+  fn save(&self, path: &Path) -> Result<(), String> {
+    let contents = ron::to_string(self).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+  }
+}
+
+#[derive(Resource)]
+struct Recorder {
+  path: PathBuf,
+  recording: Recording,
+}
+
+fn start_recording(
+  mut recorder: ResMut<Recorder>,
+  rng: Res<GameRng>,
+  navigation: Res<Navigation>,
+) {
+  recorder.recording.fixed_seed = rng.fixed_seed();
+  recorder.recording.session_seed = rng.session_seed();
+  recorder.recording.navigation = *navigation;
+}
+
+fn record_frame(
+  mut recorder: ResMut<Recorder>,
+  time: Res<Time<Real>>,
+  keys: Res<Input<KeyCode>>,
+  buttons: Res<Input<MouseButton>>,
+  mut gamepad_events: EventReader<GamepadEvent>,
+) {
+  recorder.recording.frames.push(RecordedFrame {
+    delta: time.delta(),
+    pressed_keys: keys.get_just_pressed().copied().collect(),
+    released_keys: keys.get_just_released().copied().collect(),
+    pressed_buttons: buttons.get_just_pressed().copied().collect(),
+    released_buttons: buttons.get_just_released().copied().collect(),
+    gamepad_events: gamepad_events.read().cloned().collect(),
+  });
+}
+
+fn save_recording_on_exit(
+  mut exit: EventReader<AppExit>,
+  recorder: Res<Recorder>,
+) {
+  if exit.read().next().is_some() {
+    match recorder.recording.save(&recorder.path) {
+      Ok(()) => info!("Saved recording of {} frames to {}", recorder.recording.frames.len(), recorder.path.display()),
+      Err(e) => error!("Unable to save recording to {}: {}", recorder.path.display(), e),
+    }
+  }
+}
+
+#[derive(Resource)]
+struct Playback {
+  recording: Recording,
+  cursor: usize,
+  keys: Input<KeyCode>,
+  buttons: Input<MouseButton>,
+  // Whether the current frame plays the next recorded frame. Decided a frame
+  // ahead since the time step has to be set before the frame starts.
+  advancing: bool,
+  finished: bool,
+  paused: bool,
+  step: bool,
+  speed: f32,
+  // Keep to the recorded timing rather than running flat out.
+  paced: bool,
+  recorded_clock: Duration,
+  wall_clock: Duration,
+  last_instant: Instant,
+}
+
+impl Playback {
+  fn new(recording: Recording, paced: bool) -> Self {
+    Playback {
+      advancing: !recording.frames.is_empty(),
+      finished: recording.frames.is_empty(),
+      recording,
+      cursor: 0,
+      keys: Input::default(),
+      buttons: Input::default(),
+      paused: false,
+      step: false,
+      speed: 1.,
+      paced,
+      recorded_clock: Duration::ZERO,
+      wall_clock: Duration::ZERO,
+      last_instant: Instant::now(),
+    }
+  }
+}
+
+fn playback_advancing(playback: Res<Playback>) -> bool {
+  playback.advancing || playback.finished
+}
+
+// Frames are paced by the replay rather than the display.
+fn uncap_frame_rate(
+  playback: Res<Playback>,
+  mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+  if playback.paced {
+    for mut window in windows.iter_mut() {
+      window.present_mode = PresentMode::AutoNoVsync;
+    }
+  }
+}
+
+// Recordings start on the frame loading finishes, which is when the state
+// change queued last frame is applied.
+fn holding_for_loading(state: &State<GameState>, next_state: &NextState<GameState>) -> bool {
+  *state.get() == GameState::Loading && next_state.0.is_none()
+}
+
+// Gamepad events go in ahead of the input systems, which turn them into button
+// and axis state just as for a real gamepad.
+fn play_back_gamepad_events(
+  playback: Res<Playback>,
+  state: Res<State<GameState>>,
+  next_state: Res<NextState<GameState>>,
+  mut gamepad_events: EventWriter<GamepadEvent>,
+) {
+  if playback.finished || !playback.advancing || holding_for_loading(&state, &next_state) {
+    return;
+  }
+
+  gamepad_events.send_batch(playback.recording.frames[playback.cursor].gamepad_events.iter().cloned());
+}
+
+fn play_back_input(
+  mut playback: ResMut<Playback>,
+  mut keys: ResMut<Input<KeyCode>>,
+  mut buttons: ResMut<Input<MouseButton>>,
+  mut time_strategy: ResMut<TimeUpdateStrategy>,
+  state: Res<State<GameState>>,
+  next_state: Res<NextState<GameState>>,
+) {
+  let playback = &mut *playback;
+
+  if playback.finished {
+    return;
+  }
+
+  if holding_for_loading(&state, &next_state) {
+    keys.reset_all();
+    buttons.reset_all();
+    playback.last_instant = Instant::now();
+    return;
+  }
+
+  // Live input only controls the playback itself.
+  if keys.just_pressed(KeyCode::P) {
+    playback.paused = !playback.paused;
+  }
+  if keys.just_pressed(KeyCode::Period) && playback.paused {
+    playback.step = true;
+  }
+  if keys.just_pressed(KeyCode::Equals) {
+    playback.speed = (playback.speed * 2.).min(MAX_SPEED);
+  }
+  if keys.just_pressed(KeyCode::Minus) {
+    playback.speed = (playback.speed / 2.).max(MIN_SPEED);
+  }
+
+  let now = Instant::now();
+  if !playback.paused {
+    playback.wall_clock += (now - playback.last_instant).mul_f32(playback.speed);
+  }
+  playback.last_instant = now;
+
+  playback.keys.clear();
+  playback.buttons.clear();
+
+  if playback.advancing {
+    let frame = &playback.recording.frames[playback.cursor];
+
+    for key in &frame.pressed_keys {
+      playback.keys.press(*key);
+    }
+    for key in &frame.released_keys {
+      playback.keys.release(*key);
+    }
+    for button in &frame.pressed_buttons {
+      playback.buttons.press(*button);
+    }
+    for button in &frame.released_buttons {
+      playback.buttons.release(*button);
+    }
+
+    playback.recorded_clock += frame.delta;
+    playback.cursor += 1;
+  }
+
+  *keys = playback.keys.clone();
+  *buttons = playback.buttons.clone();
+
+  let Some(next) = playback.recording.frames.get(playback.cursor) else {
+    info!("Replay finished after {} frames", playback.cursor);
+    playback.finished = true;
+    playback.advancing = false;
+    *time_strategy = TimeUpdateStrategy::Automatic;
+    return;
+  };
+
+  playback.advancing = if playback.paused {
+    if playback.step {
+      playback.wall_clock += next.delta;
+    }
+    std::mem::take(&mut playback.step)
+  } else {
+    !playback.paced || playback.recorded_clock + next.delta <= playback.wall_clock
+  };
+
+  *time_strategy = TimeUpdateStrategy::ManualDuration(if playback.advancing { next.delta } else { Duration::ZERO });
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::options::LaunchOptions;
+
+const GAMEPLAY_STREAM: u64 = 0;
+const COSMETIC_STREAM: u64 = 1;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<GameRng>();
+  }
+}
+
+// All randomness in the game comes from here so a run can be reproduced from
+// its seed. Gameplay rolls (damage, spawns) and cosmetic rolls (sound
+// variations) use separate streams, so changing what plays doesn't change how
+// the game plays out.
+#[derive(Resource)]
+pub struct GameRng {
+  seed: u64,
+  fixed_seed: Option<u64>,
+  session_seed: u64,
+  run_seeds: ChaCha8Rng,
+  pub gameplay: ChaCha8Rng,
+  pub cosmetic: ChaCha8Rng,
+}
+
+impl FromWorld for GameRng {
+  fn from_world(world: &mut World) -> Self {
+    let fixed_seed = world.get_resource::<LaunchOptions>().and_then(|options| options.seed);
+
+    GameRng::new(fixed_seed, rand::random())
+  }
+}
+
+impl GameRng {
+  // Without a fixed seed each run's seed is drawn from the session seed, so
+  // the whole session can be played back from that one number.
+  pub fn new(fixed_seed: Option<u64>, session_seed: u64) -> Self {
+    let seed = fixed_seed.unwrap_or(session_seed);
+
+    GameRng {
+      seed,
+      fixed_seed,
+      session_seed,
+      run_seeds: ChaCha8Rng::seed_from_u64(session_seed),
+      gameplay: seeded_stream(seed, GAMEPLAY_STREAM),
+      cosmetic: seeded_stream(seed, COSMETIC_STREAM),
+    }
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn fixed_seed(&self) -> Option<u64> {
+    self.fixed_seed
+  }
+
+  pub fn session_seed(&self) -> u64 {
+    self.session_seed
+  }
+
+  pub fn reseed(&mut self, seed: u64) {
+    self.seed = seed;
+    self.gameplay = seeded_stream(seed, GAMEPLAY_STREAM);
+    self.cosmetic = seeded_stream(seed, COSMETIC_STREAM);
+  }
+
+  // Every run replays the fixed seed if one was given, otherwise it gets a new one.
+  pub fn start_run(&mut self) {
+    let seed = match self.fixed_seed {
+      Some(seed) => seed,
+      None => self.run_seeds.gen(),
+    };
+    self.reseed(seed);
+  }
+}
+
+// Where a run's streams are, so a saved run rolls the same as it would have.
+// Word positions past u64 would take far longer than any run to reach.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct RngState {
+  seed: u64,
+  gameplay_position: u64,
+  cosmetic_position: u64,
+}
+
+impl GameRng {
+  pub fn state(&self) -> RngState {
+    RngState {
+      seed: self.seed,
+      gameplay_position: self.gameplay.get_word_pos() as u64,
+      cosmetic_position: self.cosmetic.get_word_pos() as u64,
+    }
+  }
+
+  pub fn restore(&mut self, state: RngState) {
+    self.reseed(state.seed);
+    self.gameplay.set_word_pos(state.gameplay_position as u128);
+    self.cosmetic.set_word_pos(state.cosmetic_position as u128);
+  }
+}
+
+fn seeded_stream(seed: u64, stream: u64) -> ChaCha8Rng {
+  let mut rng = ChaCha8Rng::seed_from_u64(seed);
+  rng.set_stream(stream);
+  rng
+}
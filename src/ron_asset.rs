@@ -0,0 +1,39 @@
+use bevy::{asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext}, prelude::*, utils::BoxedFuture};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+// Assets designers edit by hand, stored as RON.
+pub trait RonAsset: Asset + DeserializeOwned {
+  const EXTENSIONS: &'static [&'static str];
+}
+
+pub struct RonAssetLoader<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for RonAssetLoader<A> {
+  fn default() -> Self {
+    RonAssetLoader(PhantomData)
+  }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+  type Asset = A;
+  type Settings = ();
+  type Error = Box<dyn std::error::Error + Send + Sync>;
+
+  fn load<'a>(
+    &'a self,
+    reader: &'a mut Reader,
+    _settings: &'a (),
+    _load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<A, Self::Error>> {
+    Box::pin(async move {
+      let mut bytes = Vec::new();
+      reader.read_to_end(&mut bytes).await?;
+      Ok(ron::de::from_bytes(&bytes)?)
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    A::EXTENSIONS
+  }
+}
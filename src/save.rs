@@ -0,0 +1,441 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{path::{Path, PathBuf}, time::Duration};
+
+use crate::{actions::{Action, Actions}, axol::{Axol, EnemyKind}, campaign::{Campaign, CampaignLibrary}, combat::Health, enemies::{EnemyLibrary, EnemyTable}, events::{GameOverEvent, NewWaveEvent}, game::{setup_new_game, setup_spawner, Game, GameState, SimulationSet}, options::{user_data_path, LaunchOptions}, player::{setup_player, Player}, rng::{GameRng, RngState}, score::Score, spawner::{spawn_enemy, SpawnTimer, SpawnWave, Spawner, SpawnerBundle, WaveCount}, waves::{WaveLibrary, WaveTable}};
+
+// Bump whenever SaveGame changes shape. Older saves are ignored.
+const SAVE_VERSION: u32 = 2;
+
+// Saves the run at the start of every wave and on quitting, and offers to
+// continue it from the splash screen.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+  fn build(&self, app: &mut App) {
+    if !app.world.resource::<LaunchOptions>().uses_user_data() {
+      return;
+    }
+
+    let Some(slot) = SaveSlot::in_data_dir() else {
+      warn!("No user data directory, runs won't be saved");
+      return;
+    };
+
+    app.insert_resource(slot)
+       .add_systems(Update, (continue_input).run_if(in_state(GameState::Splash)).in_set(SimulationSet))
+       .add_systems(OnTransition { from: GameState::Splash, to: GameState::InGame }, (apply_deferred, restore_run).chain().after(setup_new_game).after(setup_player).run_if(resource_exists::<LoadedSave>()))
+       .add_systems(Update, (autosave.after(setup_spawner), discard_finished_run).in_set(SimulationSet))
+       .add_systems(Last, save_on_exit);
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+  version: u32,
+  wave_number: u8,
+  level: usize,
+  lives_remaining: u8,
+  score: usize,
+  high_score: usize,
+  rng: RngState,
+  player: SavedCharacter,
+  // None between waves, when the next one starts from scratch.
+  spawner: Option<SavedSpawner>,
+  axols: Vec<SavedEnemy>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedCharacter {
+  position: Vec2,
+  health: i32,
+  max_health: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEnemy {
+  // Name in the enemy table.
+  kind: String,
+  character: SavedCharacter,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedSpawner {
+  remaining: u8,
+  // Seconds into the current spawn interval.
+  elapsed: f32,
+}
+
+// Only read to check a save's version before reading the rest of it.
+#[derive(Deserialize)]
+struct SaveHeader {
+  version: u32,
+}
+
+impl SaveGame {
+  fn load(path: &Path) -> Result<Self, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    let header: SaveHeader = ron::from_str(&contents).map_err(|e| e.to_string())?;
+    if header.version != SAVE_VERSION {
+      return Err(format!("version {} is not supported, expected {}", header.version, SAVE_VERSION));
+    }
+
+    ron::from_str(&contents).map_err(|e| e.to_string())
+  }
+
+  fn save(&self, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+  }
+}
+
+// Where the run in progress is kept between sessions.
+#[derive(Resource)]
+pub struct SaveSlot {
+  path: PathBuf,
+}
+
+impl SaveSlot {
+  fn in_data_dir() -> Option<Self> {
+    user_data_path("save.ron").map(|path| SaveSlot { path })
+  }
+
+  pub fn exists(&self) -> bool {
+    self.path.is_file()
+  }
+
+  fn write(&self, save: &SaveGame) {
+    if let Err(e) = save.save(&self.path) {
+      error!("Unable to save to {}: {}", self.path.display(), e);
+    }
+  }
+
+  fn discard(&self) {
+    if self.exists() {
+      if let Err(e) = std::fs::remove_file(&self.path) {
+        error!("Unable to remove {}: {}", self.path.display(), e);
+      }
+    }
+  }
+}
+
+// Taken off the slot when continuing, until the run it holds is restored.
+#[derive(Resource)]
+struct LoadedSave(SaveGame);
+
+// Everything a save is taken from.
+#[derive(SystemParam)]
+struct RunState<'w, 's> {
+  game: Res<'w, Game>,
+  score: Res<'w, Score>,
+  rng: Res<'w, GameRng>,
+  player: Query<'w, 's, (&'static Transform, &'static Health), With<Player>>,
+  spawner: Query<'w, 's, (&'static SpawnTimer, &'static WaveCount), With<Spawner>>,
+  axols: Query<'w, 's, (&'static Transform, &'static Health, &'static EnemyKind), With<Axol>>,
+}
+
+impl RunState<'_, '_> {
+  fn snapshot(&self) -> Option<SaveGame> {
+    let save_character = |(transform, health): (&Transform, &Health)| SavedCharacter {
+      position: transform.translation.xy(),
+      health: health.0,
+      max_health: health.1,
+    };
+
+    Some(SaveGame {
+      version: SAVE_VERSION,
+      wave_number: self.game.wave_number(),
+      level: self.game.level(),
+      lives_remaining: self.game.lives_remaining(),
+      score: **self.score,
+      high_score: self.game.high_score().max(**self.score),
+      rng: self.rng.state(),
+      player: save_character(self.player.get_single().ok()?),
+      spawner: self.spawner.get_single().ok().map(|(timer, remaining)| SavedSpawner {
+        remaining: remaining.0,
+        elapsed: timer.elapsed_secs(),
+      }),
+      // Dead axols are on their way out.
+      axols: self.axols.iter()
+        .filter(|(_, health, _)| health.0 > 0)
+        .map(|(transform, health, kind)| SavedEnemy {
+          kind: kind.to_string(),
+          character: save_character((transform, health)),
+        })
+        .collect(),
+    })
+  }
+}
+
+fn continue_input(
+  mut commands: Commands,
+  slot: Res<SaveSlot>,
+  actions: Actions,
+  mut next_state: ResMut<NextState<GameState>>,
+) {
+  if actions.just_pressed(Action::Continue) && slot.exists() {
+    match SaveGame::load(&slot.path) {
+      Ok(save) => {
+        commands.insert_resource(LoadedSave(save));
+        next_state.set(GameState::InGame);
+      },
+      Err(e) => warn!("Unable to continue from {}: {}", slot.path.display(), e),
+    }
+  }
+}
+
+// Runs after the new game is set up, once its player has been spawned, and
+// replaces it with the saved one.
+fn restore_run(
+  mut commands: Commands,
+  loaded: Res<LoadedSave>,
+  mut game: ResMut<Game>,
+  mut score: ResMut<Score>,
+  mut rng: ResMut<GameRng>,
+  mut new_wave: ResMut<Events<NewWaveEvent>>,
+  mut player: Query<(&mut Transform, &mut Health), With<Player>>,
+  enemy_library: Res<EnemyLibrary>,
+  enemy_tables: Res<Assets<EnemyTable>>,
+  campaigns: Res<Assets<Campaign>>,
+  campaign_library: Res<CampaignLibrary>,
+  wave_library: Res<WaveLibrary>,
+  wave_tables: Res<Assets<WaveTable>>,
+) {
+  let save = &loaded.0;
+
+  game.resume(save.wave_number, save.level, save.lives_remaining, save.high_score);
+  *score = Score(save.score);
+  rng.restore(save.rng);
+
+  let campaign = campaigns.get(&campaign_library.campaign).expect("Campaign not loaded");
+  commands.insert_resource(campaign.selection(save.level));
+
+  let (mut transform, mut health) = player.get_single_mut().expect("Player not spawned");
+  transform.translation = save.player.position.extend(transform.translation.z);
+  *health = Health(save.player.health, save.player.max_health);
+
+  let wave = wave_tables.get(&wave_library.waves)
+    .and_then(|table| table.wave(save.wave_number as usize))
+    .expect("Wave table is empty");
+
+  let enemies = enemy_tables.get(&enemy_library.enemies).expect("Enemies not loaded");
+
+  // They wander from where they were saved until they spot the player again.
+  for axol in save.axols.iter() {
+    let Some(enemy) = enemies.get(&axol.kind) else {
+      warn!("Dropping saved {}, no such enemy type", axol.kind);
+      continue;
+    };
+
+    spawn_enemy(&mut commands, &axol.kind, enemy, axol.character.position, wave.stats)
+      .insert(Health(axol.character.health, axol.character.max_health));
+  }
+
+  // Pick the wave up part way through rather than starting it again.
+  if let Some(spawner) = &save.spawner {
+    let mut timer = Timer::from_seconds(wave.interval, TimerMode::Repeating);
+    timer.set_elapsed(Duration::from_secs_f32(spawner.elapsed));
+
+    commands.spawn(SpawnerBundle {
+      spawner: Spawner,
+      rate: SpawnTimer(timer),
+      remaining: WaveCount(spawner.remaining),
+      wave: SpawnWave(wave)
+    });
+    game.start_wave();
+    new_wave.clear();
+  }
+
+  commands.remove_resource::<LoadedSave>();
+}
+
+fn autosave(
+  slot: Res<SaveSlot>,
+  run: RunState,
+  mut event: EventReader<NewWaveEvent>,
+) {
+  if event.read().next().is_some() {
+    if let Some(save) = run.snapshot() {
+      slot.write(&save);
+    }
+  }
+}
+
+// A run that is over can't be continued. Winning the campaign doesn't end it.
+fn discard_finished_run(
+  slot: Res<SaveSlot>,
+  mut game_over: EventReader<GameOverEvent>,
+) {
+  if game_over.read().next().is_some() {
+    slot.discard();
+  }
+}
+
+fn save_on_exit(
+  slot: Res<SaveSlot>,
+  run: RunState,
+  state: Res<State<GameState>>,
+  mut exit: EventReader<AppExit>,
+) {
+  if exit.read().next().is_some() && matches!(state.get(), GameState::InGame | GameState::Paused | GameState::Victory) {
+    if let Some(save) = run.snapshot() {
+      slot.write(&save);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::system::RunSystemOnce;
+  use bevy_ecs_ldtk::LevelSelection;
+  use rand::Rng;
+  use crate::{campaign::CampaignLevel, events::VictoryEvent, waves::{WaveDefinition, WaveGrowth}};
+  use super::*;
+
+  fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("axol-{}-{}.ron", name, std::process::id()))
+  }
+
+  // A run three waves in, part way through spawning the fourth.
+  fn run_in_progress() -> World {
+    let mut world = World::new();
+
+    let mut game = Game::default();
+    game.resume(3, 1, 2, 900);
+    world.insert_resource(game);
+    world.insert_resource(Score(450));
+
+    let mut rng = GameRng::new(Some(7), 0);
+    rng.gameplay.gen::<u64>();
+    world.insert_resource(rng);
+
+    world.spawn((Player, Transform::from_xyz(120., -40., 5.), Health(14, 20)));
+
+    let mut timer = Timer::from_seconds(2., TimerMode::Repeating);
+    timer.set_elapsed(Duration::from_secs_f32(0.5));
+    world.spawn((Spawner, SpawnTimer(timer), WaveCount(4)));
+
+    world
+  }
+
+  // A freshly set up game, as continuing from the splash screen restores into.
+  fn new_game(save: SaveGame) -> World {
+    let mut world = World::new();
+
+    world.init_resource::<Game>();
+    world.insert_resource(Score(0));
+    world.insert_resource(GameRng::new(None, 1));
+    world.init_resource::<Events<NewWaveEvent>>();
+    world.send_event(NewWaveEvent);
+    world.spawn((Player, Transform::from_xyz(0., 0., 5.), Health(20, 20)));
+
+    let mut campaigns = Assets::<Campaign>::default();
+    let campaign = campaigns.add(Campaign {
+      levels: vec![
+        CampaignLevel { level: "Level_0".into(), waves: 2 },
+        CampaignLevel { level: "Level_1".into(), waves: 3 },
+      ],
+    });
+    world.insert_resource(campaigns);
+    world.insert_resource(CampaignLibrary { campaign });
+
+    let mut wave_tables = Assets::<WaveTable>::default();
+    let waves = wave_tables.add(WaveTable {
+      waves: vec![WaveDefinition { enemy: "axol".into(), count: 6, interval: 2., ..default() }],
+      beyond_last: WaveGrowth::default(),
+      bosses: None,
+    });
+    world.insert_resource(wave_tables);
+    world.insert_resource(WaveLibrary { waves });
+
+    let mut enemy_tables = Assets::<EnemyTable>::default();
+    let enemies = enemy_tables.add(ron::from_str::<EnemyTable>("(enemies: {})").unwrap());
+    world.insert_resource(enemy_tables);
+    world.insert_resource(EnemyLibrary { enemies });
+
+    world.insert_resource(LoadedSave(save));
+    world
+  }
+
+  #[test]
+  fn saved_runs_are_restored_where_they_left_off() {
+    let mut saved = run_in_progress();
+    let path = temp_path("save-round-trip");
+
+    saved.run_system_once(|run: RunState| run.snapshot()).unwrap().save(&path).unwrap();
+    let save = SaveGame::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut restored = new_game(save);
+    restored.run_system_once(restore_run);
+
+    let game = restored.resource::<Game>();
+    assert_eq!(game.wave_number(), 3);
+    assert_eq!(game.wave_reached(), 4);
+    assert_eq!(game.level(), 1);
+    assert_eq!(game.lives_remaining(), 2);
+    assert_eq!(game.high_score(), 900);
+    assert_eq!(**restored.resource::<Score>(), 450);
+    assert_eq!(restored.resource::<LevelSelection>(), &LevelSelection::Identifier("Level_1".into()));
+    assert!(restored.resource::<Events<NewWaveEvent>>().is_empty());
+    assert!(!restored.contains_resource::<LoadedSave>());
+
+    let (transform, health) = restored.query_filtered::<(&Transform, &Health), With<Player>>().single(&restored);
+    assert_eq!(transform.translation, Vec3::new(120., -40., 5.));
+    assert_eq!((health.0, health.1), (14, 20));
+
+    let (timer, remaining) = restored.query::<(&SpawnTimer, &WaveCount)>().single(&restored);
+    assert_eq!(timer.elapsed_secs(), 0.5);
+    assert_eq!(remaining.0, 4);
+
+    // Rolls carry on from where the saved run was.
+    let next_roll = |world: &mut World| world.resource_mut::<GameRng>().gameplay.gen::<u64>();
+    assert_eq!(next_roll(&mut restored), next_roll(&mut saved));
+  }
+
+  #[test]
+  fn saves_from_other_versions_are_rejected() {
+    let mut world = run_in_progress();
+    let path = temp_path("save-old-version");
+
+    let mut save = world.run_system_once(|run: RunState| run.snapshot()).unwrap();
+    save.version = SAVE_VERSION - 1;
+    save.save(&path).unwrap();
+    let loaded = SaveGame::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(loaded.is_err());
+  }
+
+  // Whether the save is still there after a frame with the given event.
+  fn kept_after<E: Event>(name: &str, event: E) -> bool {
+    let mut world = World::new();
+    let path = temp_path(name);
+    std::fs::write(&path, "()").unwrap();
+
+    world.insert_resource(SaveSlot { path: path.clone() });
+    world.init_resource::<Events<GameOverEvent>>();
+    world.init_resource::<Events<E>>();
+    world.send_event(event);
+    world.run_system_once(discard_finished_run);
+
+    let kept = world.resource::<SaveSlot>().exists();
+    let _ = std::fs::remove_file(&path);
+    kept
+  }
+
+  #[test]
+  fn game_over_discards_the_save() {
+    assert!(!kept_after("save-game-over", GameOverEvent));
+  }
+
+  #[test]
+  fn winning_keeps_the_save_for_the_endless_waves() {
+    assert!(kept_after("save-victory", VictoryEvent));
+  }
+}
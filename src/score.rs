@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{combat::Health, game::InGameSet, player::Player};
+use crate::{combat::Health, game::{Game, InGameSet}, player::Player};
 
 const SCOREBOARD_FONT_SIZE: f32 = 40.0;
 const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
@@ -12,8 +12,7 @@ pub struct ScorePlugin;
 
 impl Plugin for ScorePlugin {
   fn build(&self, app: &mut App) {
-    app.insert_resource(Score(0))
-        .add_systems(Startup, (setup_score, setup_health))
+    app.add_systems(Startup, (setup_score, setup_health))
         .add_systems(Update, (update_scoreboard, update_health).in_set(InGameSet::Score));
   }
 }
@@ -45,6 +44,19 @@ fn setup_score (
               color: SCORE_COLOR,
               ..default()
           }),
+          TextSection::new(
+              "  High: ",
+              TextStyle {
+                  font_size: SCOREBOARD_FONT_SIZE,
+                  color: TEXT_COLOR,
+                  ..default()
+              },
+          ),
+          TextSection::from_style(TextStyle {
+              font_size: SCOREBOARD_FONT_SIZE,
+              color: SCORE_COLOR,
+              ..default()
+          }),
       ])
       .with_style(Style {
           position_type: PositionType::Absolute,
@@ -55,9 +67,10 @@ fn setup_score (
   ));
 }
 
-fn update_scoreboard(score: Res<Score>, mut query: Query<&mut Text, With<ScoreboardUi>>) {
+fn update_scoreboard(score: Res<Score>, game: Res<Game>, mut query: Query<&mut Text, With<ScoreboardUi>>) {
     let mut text = query.single_mut();
     text.sections[1].value = score.to_string();
+    text.sections[3].value = game.high_score().max(**score).to_string();
 }
 
 #[derive(Component)]
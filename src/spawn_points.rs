@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{app::LdtkEntityAppExt, ldtk::{FieldValue, Level}, EntityInstance, LdtkEntity, LdtkPlugin, LevelEvent, LevelSelection};
+use rand::Rng;
+
+use crate::nowalk::{read_ldtk_project_file, selected_level};
+
+pub struct SpawnPointsPlugin;
+
+impl Plugin for SpawnPointsPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_resource::<LevelSpawnPoints>();
+
+      if app.is_plugin_added::<LdtkPlugin>() {
+        app.register_ldtk_entity::<PlayerStartBundle>("PlayerStart")
+           .register_ldtk_entity::<AxolSpawnerBundle>("AxolSpawner")
+           .add_systems(Update, cache_spawn_points);
+      } else {
+        app.add_systems(Update, (load_spawn_points_from_project_file).run_if(resource_exists_and_changed::<LevelSelection>()));
+      }
+  }
+}
+
+#[derive(Default, Component)]
+struct PlayerStart;
+
+#[derive(Default, Bundle, LdtkEntity)]
+struct PlayerStartBundle {
+  player_start: PlayerStart,
+}
+
+// An AxolSpawner placed in the level editor.
+#[derive(Default, Component, Clone, Debug)]
+pub struct AxolSpawner {
+  pub position: Vec2,
+  // Relative chance of being picked among the spawners allowed to spawn.
+  pub weight: f32,
+  // Any enemy type may spawn here when empty.
+  pub enemy_types: Vec<String>,
+  pub first_wave: u8,
+}
+
+impl AxolSpawner {
+  pub fn allows(&self, enemy: &str, wave_number: u8) -> bool {
+    wave_number >= self.first_wave
+      && self.weight > 0.
+      && (self.enemy_types.is_empty() || self.enemy_types.iter().any(|allowed| allowed == enemy))
+  }
+}
+
+impl From<&EntityInstance> for AxolSpawner {
+  fn from(entity_instance: &EntityInstance) -> Self {
+    let mut spawner = AxolSpawner { weight: 1., ..default() };
+
+    for field in entity_instance.field_instances.iter() {
+      match (field.identifier.as_str(), &field.value) {
+        ("Weight", FieldValue::Float(Some(weight))) => spawner.weight = *weight,
+        ("EnemyTypes", FieldValue::Strings(enemy_types)) => spawner.enemy_types = enemy_types.iter().flatten().cloned().collect(),
+        ("FirstWave", FieldValue::Int(Some(first_wave))) => spawner.first_wave = (*first_wave).clamp(0, u8::MAX as i32) as u8,
+        _ => (),
+      }
+    }
+
+    spawner
+  }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+struct AxolSpawnerBundle {
+  #[from_entity_instance]
+  spawner: AxolSpawner,
+}
+
+// Where the current level puts the player and spawns enemies.
+#[derive(Default, Resource)]
+pub struct LevelSpawnPoints {
+  pub player_start: Vec2,
+  pub spawners: Vec<AxolSpawner>,
+}
+
+impl LevelSpawnPoints {
+  // Picks one of the spawners allowed to spawn this enemy, weighted.
+  pub fn choose(&self, enemy: &str, wave_number: u8, rng: &mut impl Rng) -> Option<&AxolSpawner> {
+    let allowed = || self.spawners.iter().filter(|spawner| spawner.allows(enemy, wave_number));
+
+    let total: f32 = allowed().map(|spawner| spawner.weight).sum();
+    if total <= 0. {
+      return None;
+    }
+
+    let mut roll = rng.gen_range(0. ..total);
+    for spawner in allowed() {
+      if roll < spawner.weight {
+        return Some(spawner);
+      }
+      roll -= spawner.weight;
+    }
+    allowed().next_back()
+  }
+}
+
+// Center of an entity in level space, matching where bevy_ecs_ldtk places it.
+fn entity_center(entity_instance: &EntityInstance, level_height: i32) -> Vec2 {
+  let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+  let center = entity_instance.px.as_vec2() + (Vec2::splat(0.5) - entity_instance.pivot) * size;
+
+  Vec2::new(center.x, level_height as f32 - center.y)
+}
+
+fn cache_spawn_points(
+  mut spawn_points: ResMut<LevelSpawnPoints>,
+  mut level_events: EventReader<LevelEvent>,
+  player_starts: Query<&Transform, With<PlayerStart>>,
+  spawners: Query<(&Transform, &AxolSpawner)>,
+) {
+  for level_event in level_events.read() {
+    if let LevelEvent::Spawned(_) = level_event {
+      // The level is spawned at the origin, so level space is world space.
+      *spawn_points = LevelSpawnPoints {
+        player_start: player_starts.iter().next().map(|transform| transform.translation.xy()).unwrap_or_default(),
+        spawners: spawners.iter()
+          .map(|(transform, spawner)| AxolSpawner { position: transform.translation.xy(), ..spawner.clone() })
+          .collect(),
+      };
+    }
+  }
+}
+
+pub fn spawn_points_from_level(level: &Level) -> LevelSpawnPoints {
+  let mut spawn_points = LevelSpawnPoints::default();
+
+  for layer in level.layer_instances.iter().flatten() {
+    for entity_instance in layer.entity_instances.iter() {
+      let position = entity_center(entity_instance, level.px_hei);
+
+      match entity_instance.identifier.as_str() {
+        "PlayerStart" => spawn_points.player_start = position,
+        "AxolSpawner" => spawn_points.spawners.push(AxolSpawner { position, ..AxolSpawner::from(entity_instance) }),
+        _ => (),
+      }
+    }
+  }
+
+  spawn_points
+}
+
+fn load_spawn_points_from_project_file(
+  mut spawn_points: ResMut<LevelSpawnPoints>,
+  selection: Res<LevelSelection>,
+) {
+  let ldtk_json = read_ldtk_project_file();
+  let level = selected_level(&ldtk_json, &selection);
+
+  *spawn_points = spawn_points_from_level(level);
+}
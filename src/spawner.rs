@@ -1,12 +1,12 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 
-use crate::{axol::{setup_axol_animations, Axol, AxolBundle}, combat::{AttackCooldown, Health}, player::Moving, sprite::{AnimFrame, AnimState, AnimationTimer, AtlasHandles, MoveDir}};
+use crate::{axol::{Axol, AxolBundle, EnemyKind, ScoreValue}, behaviour::Ai, boss::Boss, combat::{AttackCooldown, DamageScale, Faction, Health, Strike}, enemies::{EnemyLibrary, EnemyTable, EnemyType}, game::{Game, InGameSet}, movement::{Facing, MoverBundle}, pathfinding::NavPath, rng::GameRng, spawn_points::LevelSpawnPoints, sprite::{AnimFrame, AnimState, AnimationTimer, MoveDir, ReachedFrame, SpriteTint}, waves::{StatMultipliers, WaveDefinition}};
 
 pub struct SpawnerPlugin;
 
 impl Plugin for SpawnerPlugin {
   fn build(&self, app: &mut App) {
-      app.add_systems(Update, process_wait_to_spawn);
+      app.add_systems(FixedUpdate, (process_wait_to_spawn).in_set(InGameSet::Combat));
   }
 }
 
@@ -19,49 +19,98 @@ pub struct SpawnTimer(pub Timer);
 #[derive(Default, Component, Deref, DerefMut)]
 pub struct WaveCount(pub u8);
 
+// What the spawner spawns this wave.
+#[derive(Default, Component, Deref)]
+pub struct SpawnWave(pub WaveDefinition);
+
 
 #[derive(Default, Bundle)]
 pub struct SpawnerBundle {
   pub spawner: Spawner,
   pub rate: SpawnTimer,
-  pub remaining: WaveCount
+  pub remaining: WaveCount,
+  pub wave: SpawnWave
+}
+
+// An enemy with the wave's stats applied, at full health, along with
+// whatever only some kinds have.
+pub fn spawn_enemy<'w, 's, 'a>(commands: &'a mut Commands<'w, 's>, kind: &str, enemy: &EnemyType, position: Vec2, stats: StatMultipliers) -> EntityCommands<'w, 's, 'a> {
+  let mut entity = commands.spawn(enemy_bundle(kind, enemy, position, stats));
+
+  if let Some(ranged) = &enemy.ranged {
+    entity.insert(ranged.clone());
+  }
+  if let Some(tint) = enemy.tint {
+    entity.insert(SpriteTint(tint));
+  }
+  if let Some(boss) = &enemy.boss {
+    entity.insert(Boss::new(boss, enemy.speed * stats.speed));
+  }
+
+  entity
+}
+
+fn enemy_bundle(kind: &str, enemy: &EnemyType, position: Vec2, stats: StatMultipliers) -> AxolBundle {
+  let health = (enemy.health as f32 * stats.health).round().clamp(1., i32::MAX as f32) as i32;
+  let speed = enemy.speed * stats.speed;
+  let scale = enemy.boss.as_ref().map_or(1., |boss| boss.scale);
+
+  AxolBundle {
+    axol: Axol,
+    kind: EnemyKind(kind.to_string()),
+    health: Health(health, health as u32),
+    faction: Faction::Enemy,
+    anim_state: AnimState::Idle,
+    mover: MoverBundle {
+      facing: Facing(Vec2::NEG_X),
+      ..MoverBundle::new(speed, speed)
+    },
+    collider: enemy.collider,
+    separation: enemy.separation,
+    path: NavPath::default(),
+    ai: Ai::new(position, enemy.behaviour),
+    move_dir: MoveDir::Left,
+    transform: TransformBundle::from_transform(
+      Transform::from_translation(position.extend(10.)).with_scale(Vec3::splat(scale))
+    ),
+    animations: enemy.animation_set.clone(),
+    anim_timer: AnimationTimer(Timer::from_seconds(0.8, TimerMode::Repeating)),
+    anim_frame: AnimFrame(0),
+    reached_frame: ReachedFrame::default(),
+    cooldown: AttackCooldown(Timer::from_seconds(enemy.cooldown, TimerMode::Repeating)),
+    strike: Strike::default(),
+    damage: enemy.damage,
+    damage_scale: DamageScale(stats.damage),
+    score: ScoreValue(enemy.score)
+  }
 }
 
 fn process_wait_to_spawn(
   mut commands: Commands,
   time: Res<Time>,
-  atlas_handles: Res<AtlasHandles>,
-  mut spawn_timers: Query<(&mut SpawnTimer, &mut WaveCount), With<Spawner>>
+  enemy_library: Res<EnemyLibrary>,
+  enemy_tables: Res<Assets<EnemyTable>>,
+  spawn_points: Res<LevelSpawnPoints>,
+  game: Res<Game>,
+  mut rng: ResMut<GameRng>,
+  mut spawn_timers: Query<(&mut SpawnTimer, &mut WaveCount, &SpawnWave), With<Spawner>>
 ) {
-  let sprite_axol = TextureAtlasSprite {
-    index: 0,
-    ..default()
-  };
+  let enemies = enemy_tables.get(&enemy_library.enemies).expect("Enemies not loaded");
 
-  for (mut timer, mut remaining) in spawn_timers.iter_mut() {
+  for (mut timer, mut remaining, wave) in spawn_timers.iter_mut() {
     if timer.tick(time.delta()).finished() && remaining.0 > 0 {
-      commands.spawn(
-        AxolBundle {
-          axol: Axol,
-          health: Health(20, 20),
-          anim_state: AnimState::Idle,
-          moving: Moving(false),
-          move_dir: MoveDir::Left,
-          sprite_sheet: SpriteSheetBundle {
-            sprite: sprite_axol.clone(),
-            texture_atlas: atlas_handles.handles[4].clone(),
-            transform: Transform {
-              translation: Vec3{ x: 605., y: 620., z: 10. },
-              ..default()
-            },
-            ..default()
-          },
-          animation_indices: setup_axol_animations(),
-          anim_timer: AnimationTimer(Timer::from_seconds(0.8, TimerMode::Repeating)),
-          anim_frame: AnimFrame(0),
-          cooldown: AttackCooldown(Timer::from_seconds(1.5, TimerMode::Repeating))
+      let kind = wave.next_enemy(remaining.0);
+      let spawn_point = wave.spawn_point.or_else(|| {
+        spawn_points.choose(kind, game.wave_number(), &mut rng.gameplay).map(|spawner| spawner.position)
+      });
+
+      match (enemies.get(kind), spawn_point) {
+        (_, None) => warn!("No spawner in the level allows {} in wave {}", kind, game.wave_number()),
+        (Some(enemy), Some(spawn_point)) => {
+          spawn_enemy(&mut commands, kind, enemy, spawn_point, wave.stats);
         },
-      );
+        (None, _) => warn!("Unknown enemy type {} in wave definition", kind),
+      }
 
       remaining.0 -= 1;
     }
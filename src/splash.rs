@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+use crate::{actions::{Action, InputBindings}, game::GameState, save::SaveSlot};
+
+const HINT_FONT_SIZE: f32 = 40.0;
+const HINT_TEXT_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+pub struct SplashPlugin;
+
+impl Plugin for SplashPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(GameState::Splash), splash_setup)
+       .add_systems(OnExit(GameState::Splash), despawn_screen::<OnSplashScreen>);
+  }
+}
+
+// Tag component used to tag entities added on the splash screen
+#[derive(Component)]
+struct OnSplashScreen;
+
+
+fn splash_setup(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  save_slot: Option<Res<SaveSlot>>,
+  bindings: Res<InputBindings>,
+) {
+  let mut hints = vec![format!("{} to start", bindings.describe(Action::Start))];
+  if save_slot.is_some_and(|slot| slot.exists()) {
+    hints.push(format!("{} to continue", bindings.describe(Action::Continue)));
+  }
+  hints.push(format!("{} for high scores", bindings.describe(Action::HighScores)));
+  hints.push(format!("{} for controls", bindings.describe(Action::Rebind)));
+  let icon = asset_server.load("screens/splash.png");
+  // Display the logo
+  commands
+      .spawn((
+          NodeBundle {
+              style: Style {
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  flex_direction: FlexDirection::Column,
+                  width: Val::Percent(100.0),
+                  height: Val::Percent(100.0),
+                  ..default()
+              },
+              ..default()
+          },
+          OnSplashScreen,
+      ))
+      .with_children(|parent| {
+          parent.spawn(ImageBundle {
+              style: Style {
+                  // This will set the logo to be 200px wide, and auto adjust its height
+                  width: Val::Px(1280.0),
+                  ..default()
+              },
+              image: UiImage::new(icon),
+              ..default()
+          });
+
+          parent.spawn(TextBundle::from_section(
+              format!("Press {}", hints.join(", ")),
+              TextStyle {
+                  font_size: HINT_FONT_SIZE,
+                  color: HINT_TEXT_COLOR,
+                  ..default()
+              },
+          ));
+      });
+}
+
+// Generic system that takes a component as a parameter, and will despawn all entities with that component
+pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,18 +1,34 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 use std::collections::HashMap;
 
-use crate::combat::Health;
+use crate::{combat::Health, enemies::{EnemyLibrary, EnemyTable}, events::AnimationFrameEvent, game::{GameState, InGameSet, RequiredAssets}, movement::Facing, ron_asset::{RonAsset, RonAssetLoader}};
 
+// Loads the animation definitions and steps animation state and frames. Part
+// of the simulation since attacks and deaths are timed by their animations.
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_asset::<AnimationSet>()
+         .init_asset_loader::<RonAssetLoader<AnimationSet>>()
+         .init_resource::<AnimationLibrary>()
+         .add_systems(FixedUpdate, (face_sprites, animate_sprites).chain().after(InGameSet::Movement));
+  }
+}
+
+// Loads the texture atlases and draws animated entities with them.
 pub struct SpritePlugin;
 
 impl Plugin for SpritePlugin {
   fn build(&self, app: &mut App) {
       app.add_systems(Startup, setup_atlases)
-         .add_systems(Update, animate_sprites);
+         .add_systems(OnExit(GameState::Loading), add_enemy_atlases)
+         .add_systems(Update, (attach_sprites, update_sprites).chain());
   }
 }
 
-#[derive(Component, Clone, Eq, PartialEq, Copy, Debug, Default, Hash)]
+#[derive(Component, Clone, Eq, PartialEq, Copy, Debug, Default, Hash, Deserialize)]
 pub enum AnimState {
   #[default]
   Idle,
@@ -24,39 +40,227 @@ pub enum AnimState {
   Dead
 }
 
-#[derive(Debug, Default, Component, PartialEq, Eq, Hash, Clone, Copy)]
+// The direction a sprite is drawn facing. Set from the entity's Facing to the
+// nearest direction its animation has frames for.
+#[derive(Debug, Default, Component, PartialEq, Eq, Hash, Clone, Copy, Deserialize)]
 pub enum MoveDir {
   Up,
   Left,
   Down,
   #[default]
-  Right
+  Right,
+  UpLeft,
+  UpRight,
+  DownLeft,
+  DownRight,
+}
+
+impl MoveDir {
+  // Left and Right come last so they win ties, as side-on sprites read best.
+  const ALL: [MoveDir; 8] = [
+    MoveDir::Up,
+    MoveDir::Down,
+    MoveDir::UpLeft,
+    MoveDir::UpRight,
+    MoveDir::DownLeft,
+    MoveDir::DownRight,
+    MoveDir::Left,
+    MoveDir::Right,
+  ];
+
+  pub fn to_vec2(self) -> Vec2 {
+    match self {
+      MoveDir::Up => Vec2::Y,
+      MoveDir::Left => Vec2::NEG_X,
+      MoveDir::Down => Vec2::NEG_Y,
+      MoveDir::Right => Vec2::X,
+      MoveDir::UpLeft => Vec2::new(-1., 1.).normalize(),
+      MoveDir::UpRight => Vec2::new(1., 1.).normalize(),
+      MoveDir::DownLeft => Vec2::new(-1., -1.).normalize(),
+      MoveDir::DownRight => Vec2::new(1., -1.).normalize(),
+    }
+  }
 }
 
-#[derive(Component, Clone)]
+// Every animation of one kind of entity, loaded from an `.anim.ron` file in
+// assets/animations. Edits to the file are picked up while the game runs.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct AnimationSet {
+  pub animations: HashMap<AnimState, Animation>,
+}
+
+#[derive(Deserialize)]
+pub struct Animation {
+  // Name of the texture atlas in AtlasHandles.
+  pub atlas: String,
+  pub frame_time: f32,
+  #[serde(default)]
+  pub looping: bool,
+  pub directions: HashMap<MoveDir, AnimationDirection>,
+  // Where attacks land, on the frames they land on, as drawn facing right.
+  // Directions facing left mirror them.
+  #[serde(default)]
+  pub hitboxes: Vec<FrameBox>,
+  // Where the entity can be hit. Its collider on frames without any.
+  #[serde(default)]
+  pub hurtboxes: Vec<FrameBox>,
+}
+
+#[derive(Deserialize)]
 pub struct AnimationDirection {
   pub frames: Vec<usize>,
+  #[serde(default)]
   pub flip_x: bool,
+  #[serde(default)]
   pub flip_y: bool,
-  pub looping: bool,
+  // Sent when the animation reaches a frame, by frame counting from 0, e.g.
+  // {2: ["footstep"]}.
+  #[serde(default)]
+  pub events: HashMap<usize, Vec<String>>,
+  // Whether the frames are drawn facing left, for mirroring the animation's
+  // boxes. Only needed where side-on art stands in for up or down.
+  #[serde(default)]
+  pub faces_left: Option<bool>,
+  // Replace the animation's boxes, as drawn in this direction, for art that
+  // isn't side-on, e.g. attacking up or down.
+  #[serde(default)]
+  pub hitboxes: Option<Vec<FrameBox>>,
+  #[serde(default)]
+  pub hurtboxes: Option<Vec<FrameBox>>,
 }
 
-#[derive(Default, Component, Clone)]
-pub struct AnimationIndices {
-  pub sheet_index : HashMap<AnimState, usize>,
-  pub animations : HashMap<(AnimState, MoveDir), AnimationDirection>,
-  pub timer_duration: HashMap<AnimState, f32>
+// A box on some frames of an animation, in pixels from the entity's
+// position. It grows with the sprite's scale.
+#[derive(Deserialize, Clone, Copy)]
+pub struct FrameBox {
+  // First and last frame it is on, counting from 0.
+  pub frames: (usize, usize),
+  pub half_extents: Vec2,
+  #[serde(default)]
+  pub offset: Vec2,
+}
+
+impl FrameBox {
+  fn bounds(&self, transform: &Transform, mirrored: bool) -> Rect {
+    let scale = transform.scale.truncate();
+    let offset = if mirrored { Vec2::new(-self.offset.x, self.offset.y) } else { self.offset };
+
+    Rect::from_center_half_size(transform.translation.xy() + offset * scale, self.half_extents * scale)
+  }
+}
+
+fn boxes_on(boxes: &[FrameBox], frame: usize, transform: &Transform, mirrored: bool) -> Vec<Rect> {
+  boxes.iter()
+    .filter(|frame_box| (frame_box.frames.0..=frame_box.frames.1).contains(&frame))
+    .map(|frame_box| frame_box.bounds(transform, mirrored))
+    .collect()
+}
+
+#[derive(Clone, Copy)]
+enum BoxKind {
+  Hit,
+  Hurt,
+}
+
+impl AnimationSet {
+  pub fn get(&self, anim_state: AnimState, move_dir: MoveDir) -> Option<(&Animation, &AnimationDirection)> {
+    let animation = self.animations.get(&anim_state)?;
+    let direction = animation.directions.get(&move_dir).filter(|direction| !direction.frames.is_empty())?;
+    Some((animation, direction))
+  }
+
+  // The direction's own boxes, or else the animation's, and whether they need
+  // mirroring.
+  fn boxes(&self, kind: BoxKind, anim_state: AnimState, move_dir: MoveDir) -> (&[FrameBox], bool) {
+    let Some((animation, direction)) = self.get(anim_state, move_dir) else {
+      return (&[], false);
+    };
+
+    let (own, shared) = match kind {
+      BoxKind::Hit => (&direction.hitboxes, &animation.hitboxes),
+      BoxKind::Hurt => (&direction.hurtboxes, &animation.hurtboxes),
+    };
+
+    match own {
+      Some(own) => (own, false),
+      None => (shared, direction.faces_left.unwrap_or(move_dir.to_vec2().x < 0.)),
+    }
+  }
+
+  pub fn hitboxes(&self, anim_state: AnimState, move_dir: MoveDir, frame: usize, transform: &Transform) -> Vec<Rect> {
+    let (boxes, mirrored) = self.boxes(BoxKind::Hit, anim_state, move_dir);
+    boxes_on(boxes, frame, transform, mirrored)
+  }
+
+  pub fn hurtboxes(&self, anim_state: AnimState, move_dir: MoveDir, frame: usize, transform: &Transform) -> Vec<Rect> {
+    let (boxes, mirrored) = self.boxes(BoxKind::Hurt, anim_state, move_dir);
+    boxes_on(boxes, frame, transform, mirrored)
+  }
+
+  // Whether the animation has hitboxes on this frame or any after it.
+  pub fn strikes_from(&self, anim_state: AnimState, move_dir: MoveDir, frame: usize) -> bool {
+    self.boxes(BoxKind::Hit, anim_state, move_dir).0.iter().any(|hitbox| hitbox.frames.1 >= frame)
+  }
+
+  // The direction closest to facing that the animation has frames for.
+  pub fn nearest_direction(&self, anim_state: AnimState, facing: Vec2) -> Option<MoveDir> {
+    MoveDir::ALL.into_iter()
+      .filter(|move_dir| self.get(anim_state, *move_dir).is_some())
+      .max_by(|a, b| a.to_vec2().dot(facing).total_cmp(&b.to_vec2().dot(facing)))
+  }
+}
+
+impl RonAsset for AnimationSet {
+  const EXTENSIONS: &'static [&'static str] = &["anim.ron"];
+}
+
+// Enemies load their own animation sets along with the enemy table.
+#[derive(Resource)]
+pub struct AnimationLibrary {
+  pub player: Handle<AnimationSet>,
+}
+
+impl FromWorld for AnimationLibrary {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    let library = AnimationLibrary {
+      player: asset_server.load("animations/player.anim.ron"),
+    };
+
+    world.get_resource_or_insert_with(RequiredAssets::default).add(&library.player);
+
+    library
+  }
 }
 
 #[derive(Default, Component, Deref, DerefMut)]
 pub struct AnimationTimer(pub Timer);
 
+// Multiplies the colours of the entity's sprite.
+#[derive(Component, Clone, Copy, Deref)]
+pub struct SpriteTint(pub Color);
+
 #[derive(Default, Component, PartialEq, Eq)]
 pub struct AnimFrame(pub usize);
 
+// The last frame frame events were sent for, so each is sent once on reaching
+// it however it was reached.
+#[derive(Default, Component)]
+pub struct ReachedFrame(Option<(AnimState, usize)>);
+
+// A sprite sheet cut into equal tiles, numbered along the rows.
+#[derive(Deserialize, Clone)]
+pub struct AtlasDefinition {
+  pub texture: String,
+  pub tile_size: Vec2,
+  pub columns: usize,
+  pub rows: usize,
+}
+
 #[derive(Resource, Clone)]
 pub struct AtlasHandles {
-  pub handles : [Handle<TextureAtlas>; 5]
+  pub handles : HashMap<String, Handle<TextureAtlas>>
 }
 
 pub fn setup_atlases(
@@ -64,7 +268,7 @@ pub fn setup_atlases(
   asset_server: Res<AssetServer>,
   mut atlases: ResMut<Assets<TextureAtlas>>,
 ) {
-  let mut handle_vector : Vec<Handle<TextureAtlas>> = Vec::new();
+  let mut handles : HashMap<String, Handle<TextureAtlas>> = HashMap::new();
 
   // PLAYER IDLE/IDLEINJURED
   let texture_player: Handle<Image> = asset_server.load("sprites/player/Warrior_idle.png");
@@ -77,7 +281,7 @@ pub fn setup_atlases(
     None
   );
   let handle = atlases.add(atlas_player);
-  handle_vector.push(handle);
+  handles.insert("warrior_idle".to_string(), handle);
 
   // PLAYER WALK/RUN
   let texture_player: Handle<Image> = asset_server.load("sprites/player/Warrior_walk_run.png");
@@ -90,7 +294,7 @@ pub fn setup_atlases(
     None
   );
   let handle = atlases.add(atlas_player);
-  handle_vector.push(handle);
+  handles.insert("warrior_walk_run".to_string(), handle);
 
   // PLAYER SWORD ATTACKS
   let texture_player: Handle<Image> = asset_server.load("sprites/player/Warrior_sword_attacks.png");
@@ -103,7 +307,7 @@ pub fn setup_atlases(
     None
   );
   let handle = atlases.add(atlas_player);
-  handle_vector.push(handle);
+  handles.insert("warrior_sword_attacks".to_string(), handle);
 
   // PLAYER DEATH
   let texture_player: Handle<Image> = asset_server.load("sprites/player/Warrior_death.png");
@@ -116,43 +320,73 @@ pub fn setup_atlases(
     None
   );
   let handle = atlases.add(atlas_player);
-  handle_vector.push(handle);
+  handles.insert("warrior_death".to_string(), handle);
 
+  commands.insert_resource(AtlasHandles { handles });
+}
 
-  // AXOL
-  let texture_player: Handle<Image> = asset_server.load("sprites/axol/Alien_sheet.png");
-  let atlas_player = TextureAtlas::from_grid(
-    texture_player,
-    Vec2::new(128.0, 64.0),
-    5,
-    5,
-    None,
-    None
-  );
-  let handle = atlases.add(atlas_player);
-  handle_vector.push(handle);
+// Every kind of enemy brings its own sprite sheets.
+fn add_enemy_atlases(
+  asset_server: Res<AssetServer>,
+  mut atlases: ResMut<Assets<TextureAtlas>>,
+  mut atlas_handles: ResMut<AtlasHandles>,
+  enemy_library: Res<EnemyLibrary>,
+  enemy_tables: Res<Assets<EnemyTable>>,
+) {
+  let enemies = enemy_tables.get(&enemy_library.enemies).expect("Enemies not loaded");
 
-  commands.insert_resource(AtlasHandles { handles: handle_vector.try_into().expect("Expected vector length of 5") });
+  for (name, definition) in enemies.iter().flat_map(|(_, enemy)| enemy.atlases.iter()) {
+    let atlas = TextureAtlas::from_grid(
+      asset_server.load(definition.texture.clone()),
+      definition.tile_size,
+      definition.columns,
+      definition.rows,
+      None,
+      None
+    );
+    atlas_handles.handles.insert(name.clone(), atlases.add(atlas));
+  }
+}
+
+fn face_sprites(
+  animation_sets: Res<Assets<AnimationSet>>,
+  mut query: Query<(&Facing, &AnimState, &Handle<AnimationSet>, &mut MoveDir)>
+) {
+  for (facing, anim_state, animation_set, mut move_dir) in &mut query {
+    let Some(nearest) = animation_sets.get(animation_set).and_then(|set| set.nearest_direction(*anim_state, facing.0)) else {
+      continue;
+    };
+
+    if *move_dir != nearest {
+      *move_dir = nearest;
+    }
+  }
 }
 
 pub fn animate_sprites(
   time: Res<Time>,
-  atlas_handles: Res<AtlasHandles>,
-  mut query: Query<(&mut AnimState, &MoveDir, &Health, &mut AnimFrame, &AnimationIndices, &mut AnimationTimer, &mut TextureAtlasSprite, &mut Handle<TextureAtlas>)>
+  animation_sets: Res<Assets<AnimationSet>>,
+  mut query: Query<(Entity, &mut AnimState, &MoveDir, &Health, &mut AnimFrame, &Handle<AnimationSet>, &mut AnimationTimer, &mut ReachedFrame)>,
+  mut frame_events: EventWriter<AnimationFrameEvent>,
 ) {
-  for (mut anim_state, move_dir, health, mut frame, indices, mut timer, mut sprite, mut texture_atlas) in &mut query {
+  for (entity, mut anim_state, move_dir, health, mut frame, animation_set, mut timer, mut reached) in &mut query {
+    let Some(animation_set) = animation_sets.get(animation_set) else {
+      continue;
+    };
 
     timer.tick(time.delta());
     if timer.just_finished() {
-      let info = &indices.animations[&(*anim_state, *move_dir)];
+      let Some((animation, direction)) = animation_set.get(*anim_state, *move_dir) else {
+        continue;
+      };
       let next_frame_index = 
-      if info.looping {
-        (frame.0 + 1) % info.frames.len()
-      } else if (frame.0 + 1) < info.frames.len() - 1 {
+      if animation.looping {
+        (frame.0 + 1) % direction.frames.len()
+      } else if (frame.0 + 1) < direction.frames.len() - 1 {
         frame.0 + 1
       } else if *anim_state != AnimState::Dead {
         // Not looping and at end of frames.  Go back to Idle If not Dead.
-        if health.0 < health.1 as i8 {
+        if health.0 < health.1 as i32 {
           *anim_state = AnimState::IdleInjured;
         } else {
           *anim_state = AnimState::Idle;
@@ -160,21 +394,123 @@ pub fn animate_sprites(
         0
       } else {
         // Dead... stay dead
-        info.frames.len() - 1
+        direction.frames.len() - 1
       };
-      
-      sprite.index = info.frames[next_frame_index];
 
       frame.0 = next_frame_index;
 
-      sprite.flip_x = info.flip_x;
-      sprite.flip_y = info.flip_y;
+      if let Some(animation) = animation_set.animations.get(&anim_state) {
+        *timer = AnimationTimer(Timer::from_seconds(animation.frame_time, TimerMode::Repeating));
+      }
+    }
+
+    // Also catches animations gameplay starts directly, e.g. at frame 0 for an
+    // attack, but not staying on a frame.
+    let current = (*anim_state, frame.0);
+    if reached.0 != Some(current) {
+      reached.0 = Some(current);
 
-      if *texture_atlas != atlas_handles.handles[indices.sheet_index[&anim_state]] {
-        *texture_atlas = atlas_handles.handles[indices.sheet_index[&anim_state]].clone();
+      let names = animation_set.get(*anim_state, *move_dir).and_then(|(_, direction)| direction.events.get(&frame.0));
+      for name in names.into_iter().flatten() {
+        frame_events.send(AnimationFrameEvent { entity, name: name.clone() });
       }
+    }
+  }
+}
+
+// Give newly spawned animated entities something to draw with.
+fn attach_sprites(
+  mut commands: Commands,
+  query: Query<(Entity, Option<&SpriteTint>), (With<Handle<AnimationSet>>, Without<TextureAtlasSprite>)>
+) {
+  for (entity, tint) in query.iter() {
+    commands.entity(entity).insert((
+      TextureAtlasSprite {
+        color: tint.map_or(Color::WHITE, |tint| tint.0),
+        ..default()
+      },
+      Handle::<TextureAtlas>::default(),
+      VisibilityBundle::default(),
+    ));
+  }
+}
 
-      *timer = AnimationTimer(Timer::from_seconds(indices.timer_duration[&anim_state], TimerMode::Repeating));
+fn update_sprites(
+  atlas_handles: Res<AtlasHandles>,
+  animation_sets: Res<Assets<AnimationSet>>,
+  mut query: Query<(&AnimState, &MoveDir, &AnimFrame, &Handle<AnimationSet>, &mut TextureAtlasSprite, &mut Handle<TextureAtlas>)>
+) {
+  for (anim_state, move_dir, frame, animation_set, mut sprite, mut texture_atlas) in &mut query {
+    let Some((animation, direction)) = animation_sets.get(animation_set).and_then(|set| set.get(*anim_state, *move_dir)) else {
+      continue;
+    };
+    let Some(atlas) = atlas_handles.handles.get(&animation.atlas) else {
+      continue;
+    };
+
+    sprite.index = direction.frames[frame.0.min(direction.frames.len() - 1)];
+    sprite.flip_x = direction.flip_x;
+    sprite.flip_y = direction.flip_y;
+
+    if *texture_atlas != *atlas {
+      *texture_atlas = atlas.clone();
     }
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::{event::ManualEventReader, system::RunSystemOnce};
+
+  use super::*;
+
+  fn swing() -> AnimationSet {
+    ron::from_str(r#"(
+      animations: {
+        Attack: (
+          atlas: "test",
+          frame_time: 0.1,
+          directions: {
+            Right: (frames: [0, 1, 2], events: {0: ["swing"]}),
+          },
+        ),
+      },
+    )"#).unwrap()
+  }
+
+  fn sent_events(world: &World, reader: &mut ManualEventReader<AnimationFrameEvent>) -> Vec<(Entity, String)> {
+    let events = world.resource::<Events<AnimationFrameEvent>>();
+    reader.read(events).map(|event| (event.entity, event.name.clone())).collect()
+  }
+
+  #[test]
+  fn sends_events_for_an_animation_started_at_frame_0() {
+    let mut world = World::new();
+    world.init_resource::<Time>();
+    world.init_resource::<Events<AnimationFrameEvent>>();
+
+    let mut animation_sets = Assets::<AnimationSet>::default();
+    let handle = animation_sets.add(swing());
+    world.insert_resource(animation_sets);
+
+    // As gameplay starts an attack, without animate_sprites moving it there.
+    let entity = world.spawn((
+      AnimState::Attack,
+      MoveDir::Right,
+      Health(1, 1),
+      AnimFrame(0),
+      handle,
+      AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+      ReachedFrame::default(),
+    )).id();
+
+    let mut reader = ManualEventReader::default();
+
+    world.run_system_once(animate_sprites);
+    assert_eq!(sent_events(&world, &mut reader), vec![(entity, "swing".to_string())]);
+
+    // Still on frame 0, so nothing more.
+    world.run_system_once(animate_sprites);
+    assert!(sent_events(&world, &mut reader).is_empty());
+  }
+}
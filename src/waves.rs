@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{game::RequiredAssets, ron_asset::{RonAsset, RonAssetLoader}};
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+  fn build(&self, app: &mut App) {
+      app.init_asset::<WaveTable>()
+         .init_asset_loader::<RonAssetLoader<WaveTable>>()
+         .init_resource::<WaveLibrary>();
+  }
+}
+
+// What every wave spawns, loaded from assets/waves.waves.ron.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct WaveTable {
+  pub waves: Vec<WaveDefinition>,
+  // How waves past the end of the list grow from the last one.
+  pub beyond_last: WaveGrowth,
+  // Milestone waves that end with a boss.
+  #[serde(default)]
+  pub bosses: Option<BossWaves>,
+}
+
+impl RonAsset for WaveTable {
+  const EXTENSIONS: &'static [&'static str] = &["waves.ron"];
+}
+
+#[derive(Deserialize, Clone, Default, Debug)]
+pub struct WaveDefinition {
+  pub enemy: String,
+  pub count: u8,
+  // Seconds between spawns.
+  pub interval: f32,
+  // Overrides the level's spawners, e.g. Some((605.0, 620.0)).
+  #[serde(default)]
+  pub spawn_point: Option<Vec2>,
+  #[serde(default)]
+  pub stats: StatMultipliers,
+  // Comes out after the rest of the wave.
+  #[serde(default)]
+  pub boss: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct StatMultipliers {
+  pub health: f32,
+  pub damage: f32,
+  pub speed: f32,
+}
+
+impl Default for StatMultipliers {
+  fn default() -> Self {
+    StatMultipliers { health: 1., damage: 1., speed: 1. }
+  }
+}
+
+impl WaveDefinition {
+  // Everything the wave spawns, the boss included.
+  pub fn total(&self) -> u8 {
+    self.count.saturating_add(self.boss.is_some() as u8)
+  }
+
+  // What comes out next with this many left to spawn.
+  pub fn next_enemy(&self, remaining: u8) -> &str {
+    match &self.boss {
+      Some(boss) if remaining == 1 => boss,
+      _ => &self.enemy,
+    }
+  }
+}
+
+// Every so many waves, counting from the first, another boss comes out.
+#[derive(Deserialize, Clone, Default, Debug)]
+pub struct BossWaves {
+  pub every: usize,
+  pub enemy: String,
+}
+
+// Added once per wave past the end of the list.
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+pub struct WaveGrowth {
+  #[serde(default)]
+  pub count: u8,
+  // The interval is multiplied by this, down to min_interval.
+  pub interval_factor: f32,
+  pub min_interval: f32,
+  #[serde(default)]
+  pub health: f32,
+  #[serde(default)]
+  pub damage: f32,
+  #[serde(default)]
+  pub speed: f32,
+}
+
+impl WaveTable {
+  // Waves are numbered from 0.
+  pub fn wave(&self, wave_number: usize) -> Option<WaveDefinition> {
+    let last_index = self.waves.len().checked_sub(1)?;
+    let mut wave = self.waves[wave_number.min(last_index)].clone();
+
+    let beyond = wave_number.saturating_sub(last_index) as f32;
+    let growth = &self.beyond_last;
+
+    wave.count = (wave.count as f32 + growth.count as f32 * beyond).min(u8::MAX as f32) as u8;
+    wave.interval = (wave.interval * growth.interval_factor.powf(beyond)).max(growth.min_interval.min(wave.interval));
+    wave.stats.health += growth.health * beyond;
+    wave.stats.damage += growth.damage * beyond;
+    wave.stats.speed += growth.speed * beyond;
+
+    if let Some(bosses) = self.bosses.as_ref().filter(|bosses| bosses.every > 0) {
+      if (wave_number + 1).is_multiple_of(bosses.every) {
+        wave.boss.get_or_insert_with(|| bosses.enemy.clone());
+      }
+    }
+
+    Some(wave)
+  }
+}
+
+#[derive(Resource)]
+pub struct WaveLibrary {
+  pub waves: Handle<WaveTable>,
+}
+
+impl FromWorld for WaveLibrary {
+  fn from_world(world: &mut World) -> Self {
+    let library = WaveLibrary {
+      waves: world.resource::<AssetServer>().load("waves.waves.ron"),
+    };
+
+    world.get_resource_or_insert_with(RequiredAssets::default).add(&library.waves);
+
+    library
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn table() -> WaveTable {
+    WaveTable {
+      waves: vec![
+        WaveDefinition { enemy: "axol".into(), count: 2, interval: 2., ..default() },
+        WaveDefinition { enemy: "spitter".into(), count: 4, interval: 1., ..default() },
+      ],
+      beyond_last: WaveGrowth { count: 2, interval_factor: 0.5, min_interval: 0.4, health: 0.5, ..default() },
+      bosses: None,
+    }
+  }
+
+  #[test]
+  fn listed_waves_are_used_as_is() {
+    let wave = table().wave(0).unwrap();
+
+    assert_eq!(wave.enemy, "axol");
+    assert_eq!(wave.count, 2);
+    assert_eq!(wave.interval, 2.);
+    assert_eq!(wave.stats.health, 1.);
+    assert!(wave.boss.is_none());
+  }
+
+  #[test]
+  fn waves_past_the_list_grow_from_the_last_one() {
+    let wave = table().wave(2).unwrap();
+    assert_eq!(wave.enemy, "spitter");
+    assert_eq!(wave.count, 6);
+    assert_eq!(wave.interval, 0.5);
+    assert_eq!(wave.stats.health, 1.5);
+
+    let wave = table().wave(3).unwrap();
+    assert_eq!(wave.count, 8);
+    assert_eq!(wave.interval, 0.4);
+    assert_eq!(wave.stats.health, 2.);
+  }
+
+  #[test]
+  fn every_few_waves_ends_with_a_boss() {
+    let table = WaveTable { bosses: Some(BossWaves { every: 3, enemy: "axol_king".into() }), ..table() };
+    let bosses: Vec<_> = (0..6).map(|wave_number| table.wave(wave_number).unwrap().boss).collect();
+
+    assert_eq!(bosses, vec![None, None, Some("axol_king".into()), None, None, Some("axol_king".into())]);
+  }
+
+  #[test]
+  fn listed_bosses_are_kept_on_milestone_waves() {
+    let mut table = WaveTable { bosses: Some(BossWaves { every: 3, enemy: "axol_king".into() }), ..table() };
+    table.waves[1].boss = Some("spitter".into());
+
+    assert_eq!(table.wave(2).unwrap().boss, Some("spitter".into()));
+  }
+
+  #[test]
+  fn empty_table_has_no_waves() {
+    let table = WaveTable { waves: Vec::new(), beyond_last: default(), bosses: None };
+
+    assert!(table.wave(0).is_none());
+  }
+}